@@ -11,6 +11,10 @@
 //!
 //! - `allow-unknown-fields`: When enabled, allows parsing JSON files with unknown fields.
 //!   When disabled (default), unknown fields will cause deserialization to fail.
+//! - `std`: Enables APIs that need the standard library, such as
+//!   `DevContainer::from_reader`. The rest of the crate stays `no_std`.
+//! - `ordered-env`: Backs `containerEnv` and `remoteEnv` with `OrderedEnvMap`, which
+//!   keeps variables in file order, instead of a `BTreeMap` sorted by key.
 //!
 //! ## Example
 //!
@@ -29,12 +33,48 @@
 #![no_std]
 
 extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
 
-use alloc::collections::BTreeMap;
+use alloc::collections::{BTreeMap, BTreeSet};
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 use serde::{Deserialize, Serialize};
 
+/// Key substrings marking an env variable as sensitive for [`DevContainer::sanitize_for_logging`]
+///
+/// Matching is case-insensitive.
+pub const SENSITIVE_ENV_KEY_PATTERNS: &[&str] = &["TOKEN", "SECRET", "PASSWORD", "KEY"];
+
+/// Placeholder that replaces redacted values
+pub const REDACTED: &str = "***";
+
+/// Feature IDs (see [`FeatureRef::id`]) whose metadata requires a privileged container
+///
+/// Used by [`DevContainer::implied_privileged`].
+pub const PRIVILEGED_FEATURES: &[&str] = &["ghcr.io/devcontainers/features/docker-in-docker"];
+
+/// Feature IDs (see [`FeatureRef::id`]) whose metadata requires an init process
+///
+/// Used by [`DevContainer::effective_init`].
+pub const INIT_FEATURES: &[&str] = &["ghcr.io/devcontainers/features/docker-in-docker"];
+
+/// Map type of `containerEnv` and `remoteEnv`
+///
+/// A `BTreeMap` sorted by key, or an `OrderedEnvMap` in file order when the
+/// `ordered-env` feature is enabled. Either way, converting the configuration to a
+/// `serde_json::Value` sorts env keys, since a `serde_json::Map` is ordered by key.
+#[cfg(not(feature = "ordered-env"))]
+pub type EnvMap = BTreeMap<String, String>;
+
+/// Map type of `containerEnv` and `remoteEnv`
+///
+/// An [`OrderedEnvMap`] in file order, since the `ordered-env` feature is enabled.
+/// Converting the configuration to a `serde_json::Value` still sorts env keys, since a
+/// `serde_json::Map` is ordered by key.
+#[cfg(feature = "ordered-env")]
+pub type EnvMap = OrderedEnvMap;
+
 /// Main devcontainer.json configuration structure
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
 #[non_exhaustive]
@@ -49,6 +89,9 @@ pub struct DevContainer {
     pub image: Option<String>,
 
     /// Path to a Dockerfile in your repo
+    ///
+    /// Serialized as written, never folded into `build`; see
+    /// [`DevContainer::effective_dockerfile`] for the path that applies.
     #[serde(skip_serializing_if = "Option::is_none", rename = "dockerFile")]
     pub docker_file: Option<String>,
 
@@ -60,6 +103,13 @@ pub struct DevContainer {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub features: Option<BTreeMap<String, serde_json::Value>>,
 
+    /// Order in which to install features, overriding the automatic order
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        rename = "overrideFeatureInstallOrder"
+    )]
+    pub override_feature_install_order: Option<Vec<String>>,
+
     /// VS Code extensions to install
     #[cfg(feature = "vscode")]
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -74,6 +124,10 @@ pub struct DevContainer {
     #[serde(skip_serializing_if = "Option::is_none", rename = "forwardPorts")]
     pub forward_ports: Option<Vec<PortSpec>>,
 
+    /// Ports to publish from the container
+    #[serde(skip_serializing_if = "Option::is_none", rename = "appPort")]
+    pub app_port: Option<AppPort>,
+
     /// Port attributes configuration
     #[serde(skip_serializing_if = "Option::is_none", rename = "portsAttributes")]
     pub ports_attributes: Option<BTreeMap<String, PortAttributes>>,
@@ -87,16 +141,25 @@ pub struct DevContainer {
 
     /// Environment variables for the container
     #[serde(skip_serializing_if = "Option::is_none", rename = "containerEnv")]
-    pub container_env: Option<BTreeMap<String, String>>,
+    pub container_env: Option<EnvMap>,
 
     /// Environment variables for remote processes
     #[serde(skip_serializing_if = "Option::is_none", rename = "remoteEnv")]
-    pub remote_env: Option<BTreeMap<String, String>>,
+    pub remote_env: Option<EnvMap>,
 
     /// User to run as in the container
     #[serde(skip_serializing_if = "Option::is_none", rename = "remoteUser")]
     pub remote_user: Option<String>,
 
+    /// Whether to update the remote user's UID/GID to match the local user
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        rename = "updateRemoteUserUID",
+        deserialize_with = "lenient_bool"
+    )]
+    pub update_remote_user_uid: Option<bool>,
+
     /// User for container processes
     #[serde(skip_serializing_if = "Option::is_none", rename = "containerUser")]
     pub container_user: Option<String>,
@@ -105,6 +168,17 @@ pub struct DevContainer {
     #[serde(skip_serializing_if = "Option::is_none", rename = "workspaceFolder")]
     pub workspace_folder: Option<String>,
 
+    /// Command to run when the container is first created
+    #[serde(skip_serializing_if = "Option::is_none", rename = "onCreateCommand")]
+    pub on_create_command: Option<LifecycleCommand>,
+
+    /// Command to run when new content is available in the workspace
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        rename = "updateContentCommand"
+    )]
+    pub update_content_command: Option<LifecycleCommand>,
+
     /// Command to run after container creation
     #[serde(skip_serializing_if = "Option::is_none", rename = "postCreateCommand")]
     pub post_create_command: Option<LifecycleCommand>,
@@ -117,32 +191,69 @@ pub struct DevContainer {
     #[serde(skip_serializing_if = "Option::is_none", rename = "postAttachCommand")]
     pub post_attach_command: Option<LifecycleCommand>,
 
+    /// Lifecycle command to wait for before connecting
+    #[serde(skip_serializing_if = "Option::is_none", rename = "waitFor")]
+    pub wait_for: Option<WaitFor>,
+
     /// IDE-specific customizations
     #[serde(skip_serializing_if = "Option::is_none")]
     pub customizations: Option<BTreeMap<String, serde_json::Value>>,
 
     /// Whether to use init process
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "lenient_bool"
+    )]
     pub init: Option<bool>,
 
     /// Whether to run the container in privileged mode
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "lenient_bool"
+    )]
     pub privileged: Option<bool>,
 
+    /// Linux capabilities to add to the container (e.g., "SYS_PTRACE")
+    #[serde(skip_serializing_if = "Option::is_none", rename = "capAdd")]
+    pub cap_add: Option<Vec<String>>,
+
+    /// Security options for the container (e.g., "seccomp=unconfined")
+    #[serde(skip_serializing_if = "Option::is_none", rename = "securityOpt")]
+    pub security_opt: Option<Vec<String>>,
+
     /// Whether to override the default command
-    #[serde(skip_serializing_if = "Option::is_none", rename = "overrideCommand")]
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        rename = "overrideCommand",
+        deserialize_with = "lenient_bool"
+    )]
     pub override_command: Option<bool>,
 
     /// Action to take when shutting down
     #[serde(skip_serializing_if = "Option::is_none", rename = "shutdownAction")]
     pub shutdown_action: Option<ShutdownAction>,
 
-    /// Mounts configuration
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub mounts: Option<Vec<MountSpec>>,
+    /// Mounts configuration (a single mount is accepted in place of an array)
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "one_or_many"
+    )]
+    pub mounts: Option<Vec<Mount>>,
 
     /// Run arguments for the container
-    #[serde(skip_serializing_if = "Option::is_none", rename = "runArgs")]
+    ///
+    /// A single string is accepted in place of an array and split into arguments (see
+    /// [`split_args`]).
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        rename = "runArgs",
+        deserialize_with = "args_or_string"
+    )]
     pub run_args: Option<Vec<String>>,
 
     /// Docker Compose file reference
@@ -159,414 +270,6771 @@ pub struct DevContainer {
     #[serde(skip_serializing_if = "Option::is_none", rename = "workspaceMount")]
     pub workspace_mount: Option<String>,
 
+    /// Secrets the user is prompted for, keyed by env variable name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secrets: Option<BTreeMap<String, SecretSpec>>,
+
     /// Additional unknown fields when allow-unknown-fields feature is enabled
     #[cfg(feature = "allow-unknown-fields")]
     #[serde(flatten)]
     pub additional_fields: BTreeMap<String, serde_json::Value>,
 }
 
-/// Build configuration for the dev container
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
-#[non_exhaustive]
-#[cfg_attr(not(feature = "allow-unknown-fields"), serde(deny_unknown_fields))]
-pub struct BuildConfig {
-    /// Path to Dockerfile
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub dockerfile: Option<String>,
+/// Body of [`DevContainer::walk_strings`] and [`DevContainer::walk_strings_mut`]
+///
+/// The two walkers differ only in the reference kind, so they share this body: `$iter`
+/// and `$values` are `iter`/`values` or `iter_mut`/`values_mut`, and `$walk_json` is
+/// the matching JSON walker. The exhaustive destructuring makes a new field a compile
+/// error here until it is either walked or listed as `field: _`.
+macro_rules! walk_strings_body {
+    ($this:expr, $f:ident, $iter:ident, $values:ident, $walk_json:ident) => {{
+        let DevContainer {
+            name,
+            image,
+            docker_file,
+            build,
+            features,
+            override_feature_install_order: _,
+            #[cfg(feature = "vscode")]
+            extensions,
+            #[cfg(feature = "vscode")]
+            settings,
+            forward_ports: _,
+            app_port: _,
+            ports_attributes,
+            other_ports_attributes,
+            container_env,
+            remote_env,
+            remote_user,
+            update_remote_user_uid: _,
+            container_user,
+            workspace_folder,
+            on_create_command,
+            update_content_command,
+            post_create_command,
+            post_start_command,
+            post_attach_command,
+            wait_for: _,
+            customizations,
+            init: _,
+            privileged: _,
+            cap_add,
+            security_opt,
+            override_command: _,
+            shutdown_action: _,
+            mounts,
+            run_args,
+            #[cfg(feature = "docker-compose")]
+            docker_compose_file,
+            #[cfg(feature = "docker-compose")]
+            service,
+            workspace_mount,
+            secrets: _,
+            #[cfg(feature = "allow-unknown-fields")]
+                additional_fields: _,
+        } = $this;
 
-    /// Build context path
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub context: Option<String>,
+        for value in [
+            name,
+            image,
+            docker_file,
+            remote_user,
+            container_user,
+            workspace_folder,
+            workspace_mount,
+        ] {
+            value.$iter().for_each(&mut *$f);
+        }
+        #[cfg(feature = "docker-compose")]
+        service.$iter().for_each(&mut *$f);
 
-    /// Build arguments
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub args: Option<BTreeMap<String, String>>,
+        if let Some(build) = build {
+            build.dockerfile.$iter().for_each(&mut *$f);
+            build.context.$iter().for_each(&mut *$f);
+            build.target.$iter().for_each(&mut *$f);
+            build
+                .args
+                .$iter()
+                .flat_map(|args| args.$values())
+                .for_each(&mut *$f);
+            build.cache_from.$iter().flatten().for_each(&mut *$f);
+            build
+                .labels
+                .$iter()
+                .flat_map(|labels| labels.$values())
+                .for_each(&mut *$f);
+            build.network.$iter().for_each(&mut *$f);
+        }
 
-    /// Target stage in multi-stage build
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub target: Option<String>,
+        for map in [features, customizations] {
+            map.$iter()
+                .flat_map(|map| map.$values())
+                .for_each(|value| $walk_json(value, $f));
+        }
+        #[cfg(feature = "vscode")]
+        {
+            extensions.$iter().flatten().for_each(&mut *$f);
+            settings
+                .$iter()
+                .flat_map(|map| map.$values())
+                .for_each(|value| $walk_json(value, $f));
+        }
 
-    /// Cache from configuration
-    #[serde(skip_serializing_if = "Option::is_none", rename = "cacheFrom")]
-    pub cache_from: Option<Vec<String>>,
+        ports_attributes
+            .$iter()
+            .flat_map(|attributes| attributes.$values())
+            .chain(other_ports_attributes.$iter())
+            .flat_map(|attributes| attributes.label.$iter())
+            .for_each(&mut *$f);
 
-    /// Additional unknown fields when allow-unknown-fields feature is enabled
-    #[cfg(feature = "allow-unknown-fields")]
-    #[serde(flatten)]
-    pub additional_fields: BTreeMap<String, serde_json::Value>,
-}
+        for env in [container_env, remote_env] {
+            env.$iter().flat_map(|env| env.$values()).for_each(&mut *$f);
+        }
 
-/// Service port specification
-#[derive(Debug, Clone, PartialEq)]
-pub struct ServicePort {
-    /// Service name
-    pub service: String,
-    /// Port number
-    pub port: u16,
-}
+        for command in [
+            on_create_command,
+            update_content_command,
+            post_create_command,
+            post_start_command,
+            post_attach_command,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            let specs: Vec<_> = match command {
+                LifecycleCommand::Command(spec) => alloc::vec![spec],
+                LifecycleCommand::Object(commands) => commands.$values().collect(),
+            };
+            for spec in specs {
+                match spec {
+                    CommandSpec::Shell(command) => $f(command),
+                    CommandSpec::Args(args) => args.$iter().for_each(&mut *$f),
+                }
+            }
+        }
 
-impl ServicePort {
-    /// Parse a service:port string
-    /// 
-    /// Uses rsplit_once to split on the rightmost colon, allowing service names
-    /// to contain colons. For example, "my:service:8080" parses as service="my:service", port=8080.
-    pub fn parse(s: &str) -> Option<Self> {
-        let (service, port_str) = s.rsplit_once(':')?;
-        
-        // Validate service name is not empty
-        if service.is_empty() {
-            return None;
+        for mount in mounts.$iter().flatten() {
+            match mount {
+                Mount::String(mount) => $f(mount),
+                Mount::Object(mount) => {
+                    mount.source.$iter().for_each(&mut *$f);
+                    mount.target.$iter().for_each(&mut *$f);
+                }
+            }
         }
-        
-        // Parse port
-        let port = port_str.parse::<u16>().ok()?;
-        
-        Some(ServicePort {
-            service: service.to_string(),
-            port,
-        })
-    }
+
+        for list in [cap_add, security_opt, run_args] {
+            list.$iter().flatten().for_each(&mut *$f);
+        }
+
+        #[cfg(feature = "docker-compose")]
+        match docker_compose_file {
+            Some(DockerComposeFile::String(file)) => $f(file),
+            Some(DockerComposeFile::Array(files)) => files.$iter().for_each(&mut *$f),
+            None => {}
+        }
+    }};
 }
 
-impl core::fmt::Display for ServicePort {
-    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        write!(f, "{}:{}", self.service, self.port)
+impl DevContainer {
+    /// Parse a devcontainer.json document from a reader
+    #[cfg(feature = "std")]
+    pub fn from_reader<R: std::io::Read>(reader: R) -> Result<DevContainer, ParseError> {
+        Ok(DevContainer::parsed(serde_json::from_reader(reader)?))
     }
-}
 
-impl Serialize for ServicePort {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        serializer.serialize_str(&self.to_string())
+    /// Parse a devcontainer.json document from bytes
+    ///
+    /// A leading UTF-8 byte order mark and whitespace are ignored.
+    pub fn from_slice(input: &[u8]) -> Result<DevContainer, ParseError> {
+        let input = input.strip_prefix(UTF8_BOM.as_bytes()).unwrap_or(input);
+        let devcontainer = serde_json::from_slice(input.trim_ascii_start())?;
+        Ok(DevContainer::parsed(devcontainer))
     }
-}
 
-impl<'de> Deserialize<'de> for ServicePort {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        let s = String::deserialize(deserializer)?;
-        ServicePort::parse(&s)
-            .ok_or_else(|| serde::de::Error::custom("Expected format 'service:port'"))
+    /// Parse a devcontainer.json document that may contain `//` and `/* */` comments
+    ///
+    /// See [`strip_jsonc_comments`]. As with [`str::parse`], a leading UTF-8 byte order
+    /// mark and whitespace are ignored.
+    pub fn from_jsonc(input: &str) -> Result<DevContainer, ParseError> {
+        let devcontainer = serde_json::from_str(&strip_jsonc_comments(trim_preamble(input)))?;
+        Ok(DevContainer::parsed(devcontainer))
     }
-}
 
-/// Port specification (can be a number or service:port)
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(untagged)]
-pub enum PortSpec {
-    /// Numeric port
-    Number(u16),
-    /// Service name with port specification (e.g., "db:5432")
-    Service(ServicePort),
-}
+    /// Normalization applied by the crate's parse entry points
+    fn parsed(mut devcontainer: DevContainer) -> DevContainer {
+        devcontainer.normalize_port_labels();
+        devcontainer
+    }
 
-/// Port attributes configuration
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
-#[non_exhaustive]
-#[cfg_attr(not(feature = "allow-unknown-fields"), serde(deny_unknown_fields))]
-pub struct PortAttributes {
-    /// Label for the port
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub label: Option<String>,
+    /// Replace object-form `forwardPorts` entries with plain port numbers
+    ///
+    /// Each entry's label moves to the `portsAttributes` entry for its port, unless that
+    /// entry already has a label. Called by the crate's parse entry points (e.g.
+    /// [`str::parse`] and [`Self::from_jsonc`]), but not by plain `serde` deserialization,
+    /// which keeps [`PortSpec::Labeled`] entries as written.
+    pub fn normalize_port_labels(&mut self) {
+        for spec in self.forward_ports.iter_mut().flatten() {
+            let PortSpec::Labeled(labeled) = spec else {
+                continue;
+            };
+            let port = labeled.port;
+            if let Some(label) = labeled.label.take() {
+                let attributes = self
+                    .ports_attributes
+                    .get_or_insert_with(BTreeMap::new)
+                    .entry(port.to_string())
+                    .or_default();
+                attributes.label.get_or_insert(label);
+            }
+            *spec = PortSpec::Number(port);
+        }
+    }
 
-    /// Protocol for the port
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub protocol: Option<String>,
+    /// Deduplicate and sort the numeric entries of `forwardPorts`
+    ///
+    /// Numbers and ranges come first in ascending order: duplicates are dropped, ports
+    /// inside a range are absorbed by it, and overlapping or adjacent ranges are merged.
+    /// Adjacent plain numbers stay separate entries. Host-qualified, labeled and service
+    /// ports follow in their original order.
+    pub fn normalize_forward_ports(&mut self) {
+        let Some(ports) = self.forward_ports.take() else {
+            return;
+        };
+        let mut numeric = Vec::new();
+        let mut rest = Vec::new();
+        for spec in ports {
+            match spec {
+                PortSpec::Number(port) => numeric.push((port, port)),
+                PortSpec::Range(range) => numeric.push((range.start, range.end)),
+                other => rest.push(other),
+            }
+        }
 
-    /// Action when auto-forwarding
-    #[serde(skip_serializing_if = "Option::is_none", rename = "onAutoForward")]
-    pub on_auto_forward: Option<String>,
+        let mut normalized: Vec<PortSpec> = merge_port_ranges(numeric)
+            .into_iter()
+            .map(|(start, end)| {
+                if start == end {
+                    PortSpec::Number(start)
+                } else {
+                    PortSpec::Range(PortRange { start, end })
+                }
+            })
+            .collect();
+        normalized.extend(rest);
+        self.forward_ports = Some(normalized);
+    }
 
-    /// Whether to require local port
-    #[serde(skip_serializing_if = "Option::is_none", rename = "requireLocalPort")]
-    pub require_local_port: Option<bool>,
+    /// New configuration with only `features` and `overrideFeatureInstallOrder` copied
+    ///
+    /// Useful for extracting a feature manifest; every other field is left unset.
+    pub fn as_features_only(&self) -> DevContainer {
+        DevContainer {
+            features: self.features.clone(),
+            override_feature_install_order: self.override_feature_install_order.clone(),
+            ..Default::default()
+        }
+    }
 
-    /// Elevate if needed
-    #[serde(skip_serializing_if = "Option::is_none", rename = "elevateIfNeeded")]
-    pub elevate_if_needed: Option<bool>,
+    /// Whether only `name` and an image source (`image`, `dockerFile` or `build`) are set
+    ///
+    /// Lets tools fast-path trivial configurations. Every other field, including
+    /// captured unknown fields, must be unset; an empty collection counts as set.
+    pub fn is_minimal(&self) -> bool {
+        let DevContainer {
+            name: _,
+            image,
+            docker_file,
+            build,
+            features,
+            override_feature_install_order,
+            #[cfg(feature = "vscode")]
+            extensions,
+            #[cfg(feature = "vscode")]
+            settings,
+            forward_ports,
+            app_port,
+            ports_attributes,
+            other_ports_attributes,
+            container_env,
+            remote_env,
+            remote_user,
+            update_remote_user_uid,
+            container_user,
+            workspace_folder,
+            on_create_command,
+            update_content_command,
+            post_create_command,
+            post_start_command,
+            post_attach_command,
+            wait_for,
+            customizations,
+            init,
+            privileged,
+            cap_add,
+            security_opt,
+            override_command,
+            shutdown_action,
+            mounts,
+            run_args,
+            #[cfg(feature = "docker-compose")]
+            docker_compose_file,
+            #[cfg(feature = "docker-compose")]
+            service,
+            workspace_mount,
+            secrets,
+            #[cfg(feature = "allow-unknown-fields")]
+            additional_fields,
+        } = self;
 
-    /// Additional unknown fields when allow-unknown-fields feature is enabled
-    #[cfg(feature = "allow-unknown-fields")]
-    #[serde(flatten)]
-    pub additional_fields: BTreeMap<String, serde_json::Value>,
-}
+        #[cfg(feature = "vscode")]
+        if extensions.is_some() || settings.is_some() {
+            return false;
+        }
+        #[cfg(feature = "docker-compose")]
+        if docker_compose_file.is_some() || service.is_some() {
+            return false;
+        }
+        #[cfg(feature = "allow-unknown-fields")]
+        if !additional_fields.is_empty() {
+            return false;
+        }
 
-/// Basic command specification (shell or args)
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(untagged)]
-pub enum CommandSpec {
-    /// Shell command string
-    Shell(String),
-    /// Array of command arguments
-    Args(Vec<String>),
-}
+        let has_image_source = image.is_some() || docker_file.is_some() || build.is_some();
+        has_image_source
+            && [
+                features.is_none(),
+                override_feature_install_order.is_none(),
+                forward_ports.is_none(),
+                app_port.is_none(),
+                ports_attributes.is_none(),
+                other_ports_attributes.is_none(),
+                container_env.is_none(),
+                remote_env.is_none(),
+                remote_user.is_none(),
+                update_remote_user_uid.is_none(),
+                container_user.is_none(),
+                workspace_folder.is_none(),
+                on_create_command.is_none(),
+                update_content_command.is_none(),
+                post_create_command.is_none(),
+                post_start_command.is_none(),
+                post_attach_command.is_none(),
+                wait_for.is_none(),
+                customizations.is_none(),
+                init.is_none(),
+                privileged.is_none(),
+                cap_add.is_none(),
+                security_opt.is_none(),
+                override_command.is_none(),
+                shutdown_action.is_none(),
+                mounts.is_none(),
+                run_args.is_none(),
+                workspace_mount.is_none(),
+                secrets.is_none(),
+            ]
+            .into_iter()
+            .all(|unset| unset)
+    }
 
-/// Lifecycle command specification (can be a command or object of commands)
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(untagged)]
-pub enum LifecycleCommand {
-    /// Single command
-    Command(CommandSpec),
-    /// Object with multiple named commands
-    Object(BTreeMap<String, CommandSpec>),
-}
+    /// Summary of the settings that grant the container extra privileges
+    pub fn count_privileged_capabilities(&self) -> PrivilegeReport {
+        PrivilegeReport {
+            privileged: self.privileged == Some(true),
+            cap_add: self.cap_add.as_ref().map_or(0, Vec::len),
+            security_opt: self.security_opt.as_ref().map_or(0, Vec::len),
+        }
+    }
 
-/// Shutdown action
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[non_exhaustive]
-#[serde(rename_all = "camelCase")]
-pub enum ShutdownAction {
-    /// No action
-    None,
-    /// Stop the container
-    StopContainer,
-    /// Stop Docker Compose
-    #[cfg(feature = "docker-compose")]
-    StopCompose,
-}
+    /// Number of entries in `features`
+    pub fn feature_count(&self) -> usize {
+        self.features.as_ref().map_or(0, BTreeMap::len)
+    }
 
-/// Mount specification
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
-#[non_exhaustive]
-#[cfg_attr(not(feature = "allow-unknown-fields"), serde(deny_unknown_fields))]
-pub struct MountSpec {
-    /// Source path
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub source: Option<String>,
+    /// Whether any feature is declared
+    pub fn has_features(&self) -> bool {
+        self.feature_count() > 0
+    }
 
-    /// Target path in container
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub target: Option<String>,
+    /// Number of entries in `forwardPorts` (a range counts once)
+    pub fn forwarded_port_count(&self) -> usize {
+        self.forward_ports.as_ref().map_or(0, Vec::len)
+    }
 
-    /// Mount type
-    #[serde(skip_serializing_if = "Option::is_none", rename = "type")]
-    pub mount_type: Option<String>,
+    /// Number of entries in `mounts`
+    pub fn mount_count(&self) -> usize {
+        self.mounts.as_ref().map_or(0, Vec::len)
+    }
 
-    /// Additional unknown fields when allow-unknown-fields feature is enabled
-    #[cfg(feature = "allow-unknown-fields")]
-    #[serde(flatten)]
-    pub additional_fields: BTreeMap<String, serde_json::Value>,
-}
+    /// Host source paths of bind mounts, for the caller to check on disk
+    ///
+    /// Both short-form and object mounts count, but only with an explicit `type=bind`.
+    /// Sources that are a single `${...}` variable (e.g. `${localEnv:HOME}`) are
+    /// skipped since they can't be checked before substitution.
+    pub fn bind_mount_sources(&self) -> Vec<&str> {
+        self.mounts
+            .iter()
+            .flatten()
+            .filter_map(|mount| match mount {
+                Mount::String(s) => {
+                    let mut bind = false;
+                    let mut source = None;
+                    for (key, value) in short_form_pairs(s) {
+                        match key {
+                            "type" => bind = value == "bind",
+                            "source" | "src" => source = Some(value),
+                            _ => {}
+                        }
+                    }
+                    source.filter(|_| bind)
+                }
+                Mount::Object(spec) => spec
+                    .source
+                    .as_deref()
+                    .filter(|_| spec.mount_type == Some(MountType::Bind)),
+            })
+            .filter(|source| {
+                let variable_only =
+                    source.starts_with("${") && source.find('}') == Some(source.len() - 1);
+                !source.is_empty() && !variable_only
+            })
+            .collect()
+    }
 
-/// Docker Compose file specification
-#[cfg(feature = "docker-compose")]
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[serde(untagged)]
-pub enum DockerComposeFile {
-    /// Single file path
-    String(String),
-    /// Array of file paths
-    Array(Vec<String>),
-}
+    /// The workspace mount, synthesizing the spec default when `workspaceMount` is unset
+    ///
+    /// The default binds `${localWorkspaceFolder}` to `workspaceFolder`, or to
+    /// `/workspaces/${localWorkspaceFolderBasename}` if that is unset too. Variables are
+    /// left unsubstituted. Returns `None` only when an explicit `workspaceMount` doesn't parse.
+    pub fn effective_workspace_mount(&self) -> Option<MountSpec> {
+        if let Some(workspace_mount) = &self.workspace_mount {
+            return MountSpec::parse(workspace_mount);
+        }
+        let target = self
+            .workspace_folder
+            .clone()
+            .unwrap_or_else(|| "/workspaces/${localWorkspaceFolderBasename}".to_string());
+        Some(MountSpec {
+            source: Some("${localWorkspaceFolder}".to_string()),
+            target: Some(target),
+            mount_type: Some(MountType::Bind),
+            ..Default::default()
+        })
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use alloc::string::ToString;
-    #[cfg(feature = "vscode")]
-    use alloc::vec;
+    /// Canonical feature IDs declared by more than one key in `features`
+    ///
+    /// Keys are compared via [`FeatureRef::canonical`], so `.../node` and `.../node:latest`
+    /// collide. Keys that don't parse as a feature reference are compared verbatim.
+    pub fn duplicate_features(&self) -> Vec<String> {
+        let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+        for key in self.features.iter().flat_map(|features| features.keys()) {
+            let canonical = FeatureRef::parse(key)
+                .map(|feature| feature.canonical())
+                .unwrap_or_else(|| key.clone());
+            *counts.entry(canonical).or_insert(0) += 1;
+        }
+        counts
+            .into_iter()
+            .filter(|(_, count)| *count > 1)
+            .map(|(canonical, _)| canonical)
+            .collect()
+    }
 
-    #[test]
-    fn test_basic_devcontainer() {
-        let json = r#"{
-            "name": "Test Container",
-            "image": "mcr.microsoft.com/devcontainers/rust:latest"
-        }"#;
+    /// Feature keys pinned below the minimum version wanted for their ID
+    ///
+    /// `min_versions` maps versionless feature IDs (see [`FeatureRef::id`]) to the
+    /// minimum version; comparison follows [`FeatureRef::version_satisfies`]. Keys
+    /// that don't parse as a feature reference are ignored.
+    pub fn features_below(&self, min_versions: &BTreeMap<String, String>) -> Vec<String> {
+        self.features
+            .iter()
+            .flat_map(|features| features.keys())
+            .filter(|key| {
+                FeatureRef::parse(key).is_some_and(|feature| {
+                    min_versions
+                        .get(&feature.id())
+                        .is_some_and(|wanted| !feature.version_satisfies(wanted))
+                })
+            })
+            .cloned()
+            .collect()
+    }
 
-        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
-        assert_eq!(devcontainer.name, Some("Test Container".to_string()));
-        assert_eq!(
-            devcontainer.image,
+    /// Feature keys that reference a local folder (see [`FeatureRef::Local`])
+    pub fn local_features(&self) -> Vec<&String> {
+        self.features
+            .iter()
+            .flat_map(|features| features.keys())
+            .filter(|key| matches!(FeatureRef::parse(key), Some(FeatureRef::Local { .. })))
+            .collect()
+    }
+
+    /// Whether the container runs privileged, given `privileged` and the declared features
+    ///
+    /// This is a heuristic: feature metadata isn't available here, so a feature is
+    /// assumed to require privileged mode when its ID is in [`PRIVILEGED_FEATURES`].
+    /// As with real feature metadata, such a feature wins over an explicit
+    /// `"privileged": false`.
+    pub fn implied_privileged(&self) -> bool {
+        self.privileged == Some(true) || self.has_feature_in(PRIVILEGED_FEATURES)
+    }
+
+    /// Whether the container runs an init process, given `init` and the declared features
+    ///
+    /// Follows the same heuristic as [`Self::implied_privileged`], using
+    /// [`INIT_FEATURES`].
+    pub fn effective_init(&self) -> bool {
+        self.init == Some(true) || self.has_feature_in(INIT_FEATURES)
+    }
+
+    /// Whether any declared feature's ID is in `ids`
+    fn has_feature_in(&self, ids: &[&str]) -> bool {
+        self.features
+            .iter()
+            .flat_map(|features| features.keys())
+            .filter_map(|key| FeatureRef::parse(key))
+            .any(|feature| ids.contains(&feature.id().as_str()))
+    }
+
+    /// Deserialize a single option of the feature declared under the key `feature`
+    ///
+    /// Returns `None` if the feature isn't declared, its value isn't an options object,
+    /// or the option is absent.
+    pub fn feature_option<T: serde::de::DeserializeOwned>(
+        &self,
+        feature: &str,
+        option: &str,
+    ) -> Option<Result<T, serde_json::Error>> {
+        self.feature_options(feature)?.get(option)
+    }
+
+    /// Apply a JSON Pointer (RFC 6901) to the value of the feature declared under `feature`
+    ///
+    /// The pointer is resolved against the whole options value, so `/version` reads
+    /// the `version` option and `/packages/0` the first element of a `packages` array.
+    /// The empty pointer returns the value itself.
+    pub fn feature_option_pointer(
+        &self,
+        feature: &str,
+        pointer: &str,
+    ) -> Option<&serde_json::Value> {
+        self.features.as_ref()?.get(feature)?.pointer(pointer)
+    }
+
+    /// Move the feature declared under `old_key` to `new_key`, keeping its options
+    ///
+    /// Meant for version bumps such as `.../node:2` to `.../node:3`. An
+    /// `overrideFeatureInstallOrder` entry naming `old_key` is replaced in place by
+    /// `new_key`; a versionless entry is replaced by the new feature ID only if the ID
+    /// changes. An existing `new_key` is overwritten. Returns `false` if `old_key`
+    /// isn't declared.
+    pub fn rename_feature(&mut self, old_key: &str, new_key: &str) -> bool {
+        let Some(features) = self.features.as_mut() else {
+            return false;
+        };
+        let Some(options) = features.remove(old_key) else {
+            return false;
+        };
+        features.insert(new_key.to_string(), options);
+
+        let old_id = feature_identity(old_key);
+        let new_id = feature_identity(new_key);
+        for entry in self.override_feature_install_order.iter_mut().flatten() {
+            if entry == old_key {
+                *entry = new_key.to_string();
+            } else if *entry == old_id && old_id != new_id {
+                *entry = new_id.clone();
+            }
+        }
+        true
+    }
+
+    /// Options of the feature declared under the key `feature`
+    ///
+    /// Returns `None` if the feature isn't declared or its value isn't an options object.
+    pub fn feature_options(&self, feature: &str) -> Option<FeatureOptions<'_>> {
+        let options = self.features.as_ref()?.get(feature)?.as_object()?;
+        Some(FeatureOptions { options })
+    }
+
+    /// Flattened execution plan across all lifecycle phases
+    ///
+    /// Entries are ordered by phase. Within a phase, the commands of a parallel object
+    /// are labelled with their name (in key order) and may be run concurrently; a
+    /// single command has no label.
+    pub fn execution_plan(&self) -> Vec<(LifecyclePhase, Option<&str>, &CommandSpec)> {
+        let mut plan = Vec::new();
+        for (phase, command) in self.lifecycle_commands() {
+            match command {
+                LifecycleCommand::Command(spec) => plan.push((phase, None, spec)),
+                LifecycleCommand::Object(commands) => plan.extend(
+                    commands
+                        .iter()
+                        .map(|(name, spec)| (phase, Some(name.as_str()), spec)),
+                ),
+            }
+        }
+        plan
+    }
+
+    /// Lifecycle commands grouped by phase, for running groups in sequence
+    ///
+    /// Groups are in phase order and the commands within a group may run concurrently.
+    /// A parallel object contributes its named commands (in key order); a single command
+    /// forms a one-element group named after its phase (e.g. `"postCreateCommand"`).
+    /// Unset phases and empty objects produce no group.
+    pub fn split_lifecycle_parallel(&self) -> Vec<Vec<(String, CommandSpec)>> {
+        self.lifecycle_commands()
+            .map(|(phase, command)| command.to_named(phase.as_str()).into_iter().collect())
+            .filter(|group: &Vec<_>| !group.is_empty())
+            .collect()
+    }
+
+    /// `containerEnv` entries ordered so that referenced variables precede their referrers
+    ///
+    /// A value references another variable through `${containerEnv:NAME}`, `${NAME}` or
+    /// `$NAME`. Only references to other keys of `containerEnv` create an ordering
+    /// constraint; a self-reference such as `PATH=${containerEnv:PATH}:/opt/bin` refers
+    /// to the image's value and is ignored. Unconstrained keys keep the map's order
+    /// (alphabetical, or file order with `ordered-env`).
+    pub fn topologically_ordered_env(&self) -> Result<Vec<(String, String)>, EnvCycleError> {
+        let Some(env) = &self.container_env else {
+            return Ok(Vec::new());
+        };
+
+        let dependencies: BTreeMap<&str, Vec<&str>> = env
+            .iter()
+            .map(|(key, value)| {
+                let referenced = env_references(value)
+                    .into_iter()
+                    .filter(|name| *name != key && env.contains_key(*name))
+                    .collect();
+                (key.as_str(), referenced)
+            })
+            .collect();
+
+        let mut ordered: Vec<(String, String)> = Vec::with_capacity(env.len());
+        let mut remaining: Vec<&str> = env.keys().map(String::as_str).collect();
+        while !remaining.is_empty() {
+            let ready = remaining.iter().position(|key| {
+                dependencies[key]
+                    .iter()
+                    .all(|dependency| ordered.iter().any(|(done, _)| done == dependency))
+            });
+            let Some(index) = ready else {
+                return Err(EnvCycleError {
+                    keys: remaining.into_iter().map(String::from).collect(),
+                });
+            };
+            let key = remaining.remove(index);
+            ordered.push((key.to_string(), env[key].clone()));
+        }
+        Ok(ordered)
+    }
+
+    /// Merge `other` on top of this configuration
+    ///
+    /// Fields set in `other` replace the ones set here, except that maps are combined:
+    /// `portsAttributes`, `containerEnv`, `remoteEnv`, `secrets`, `build.args` and
+    /// `build.labels` are merged key by key with `other` winning, while `features`,
+    /// `customizations` (and the legacy `settings`) are deep-merged so nested objects
+    /// combine recursively and only non-object values from `other` replace existing
+    /// ones. For `features`, this means the options given for the same feature key on
+    /// both sides are combined.
+    pub fn merge(&mut self, other: &DevContainer) {
+        let DevContainer {
+            name,
+            image,
+            docker_file,
+            build,
+            features,
+            override_feature_install_order,
+            #[cfg(feature = "vscode")]
+            extensions,
+            #[cfg(feature = "vscode")]
+            settings,
+            forward_ports,
+            app_port,
+            ports_attributes,
+            other_ports_attributes,
+            container_env,
+            remote_env,
+            remote_user,
+            update_remote_user_uid,
+            container_user,
+            workspace_folder,
+            on_create_command,
+            update_content_command,
+            post_create_command,
+            post_start_command,
+            post_attach_command,
+            wait_for,
+            customizations,
+            init,
+            privileged,
+            cap_add,
+            security_opt,
+            override_command,
+            shutdown_action,
+            mounts,
+            run_args,
+            #[cfg(feature = "docker-compose")]
+            docker_compose_file,
+            #[cfg(feature = "docker-compose")]
+            service,
+            workspace_mount,
+            secrets,
+            #[cfg(feature = "allow-unknown-fields")]
+            additional_fields,
+        } = other;
+
+        merge_replace(&mut self.name, name);
+        merge_replace(&mut self.image, image);
+        merge_replace(&mut self.docker_file, docker_file);
+        match (&mut self.build, build) {
+            (Some(base), Some(build)) => base.merge(build),
+            (base, build) => merge_replace(base, build),
+        }
+        merge_deep(&mut self.features, features);
+        merge_replace(
+            &mut self.override_feature_install_order,
+            override_feature_install_order,
+        );
+        #[cfg(feature = "vscode")]
+        merge_replace(&mut self.extensions, extensions);
+        #[cfg(feature = "vscode")]
+        merge_deep(&mut self.settings, settings);
+        merge_replace(&mut self.forward_ports, forward_ports);
+        merge_replace(&mut self.app_port, app_port);
+        merge_keys(&mut self.ports_attributes, ports_attributes);
+        merge_replace(&mut self.other_ports_attributes, other_ports_attributes);
+        merge_env(&mut self.container_env, container_env);
+        merge_env(&mut self.remote_env, remote_env);
+        merge_replace(&mut self.remote_user, remote_user);
+        merge_replace(&mut self.update_remote_user_uid, update_remote_user_uid);
+        merge_replace(&mut self.container_user, container_user);
+        merge_replace(&mut self.workspace_folder, workspace_folder);
+        merge_replace(&mut self.on_create_command, on_create_command);
+        merge_replace(&mut self.update_content_command, update_content_command);
+        merge_replace(&mut self.post_create_command, post_create_command);
+        merge_replace(&mut self.post_start_command, post_start_command);
+        merge_replace(&mut self.post_attach_command, post_attach_command);
+        merge_replace(&mut self.wait_for, wait_for);
+        merge_deep(&mut self.customizations, customizations);
+        merge_replace(&mut self.init, init);
+        merge_replace(&mut self.privileged, privileged);
+        merge_replace(&mut self.cap_add, cap_add);
+        merge_replace(&mut self.security_opt, security_opt);
+        merge_replace(&mut self.override_command, override_command);
+        merge_replace(&mut self.shutdown_action, shutdown_action);
+        merge_replace(&mut self.mounts, mounts);
+        merge_replace(&mut self.run_args, run_args);
+        #[cfg(feature = "docker-compose")]
+        merge_replace(&mut self.docker_compose_file, docker_compose_file);
+        #[cfg(feature = "docker-compose")]
+        merge_replace(&mut self.service, service);
+        merge_replace(&mut self.workspace_mount, workspace_mount);
+        merge_keys(&mut self.secrets, secrets);
+        #[cfg(feature = "allow-unknown-fields")]
+        self.additional_fields.extend(
+            additional_fields
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone())),
+        );
+    }
+
+    /// Check the configuration for errors
+    ///
+    /// Runs [`Self::lint`] and returns its [`Severity::Error`] diagnostics, if any.
+    pub fn validate(&self) -> Result<(), Vec<Diagnostic>> {
+        let errors: Vec<Diagnostic> = self
+            .lint()
+            .into_iter()
+            .filter(|diagnostic| diagnostic.severity == Severity::Error)
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Check the configuration for likely mistakes
+    pub fn lint(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        self.lint_remote_user_uid(&mut diagnostics);
+        self.lint_dockerfile_conflict(&mut diagnostics);
+        self.lint_wait_for(&mut diagnostics);
+        self.lint_mount_targets(&mut diagnostics);
+        self.lint_feature_install_order(&mut diagnostics);
+        diagnostics.extend(self.validate_image_reference());
+        diagnostics
+    }
+
+    /// Diagnostic if `image` is not a valid image reference (see [`ImageRef::parse`])
+    ///
+    /// A reference that is only invalid because of uppercase letters (e.g. `"Ubuntu"`)
+    /// gets a warning; any other malformed reference is an error, since no tool could
+    /// pull it. References containing a `${...}` variable are only checked after
+    /// substitution. `build` has no image name of its own, so only `image` is checked.
+    pub fn validate_image_reference(&self) -> Option<Diagnostic> {
+        let image = self.image.as_ref()?;
+        if image.contains("${") || ImageRef::parse(image).is_some() {
+            return None;
+        }
+        if ImageRef::parse(&image.to_ascii_lowercase()).is_some() {
+            return Some(Diagnostic::warning(
+                "image",
+                alloc::format!(
+                    "{:?} must be lowercase to be a valid image reference",
+                    image
+                ),
+            ));
+        }
+        Some(Diagnostic::error(
+            "image",
+            alloc::format!("{:?} is not a valid image reference", image),
+        ))
+    }
+
+    /// `updateRemoteUserUID` has no effect without a `remoteUser`
+    fn lint_remote_user_uid(&self, diagnostics: &mut Vec<Diagnostic>) {
+        if self.update_remote_user_uid == Some(true) && self.remote_user.is_none() {
+            diagnostics.push(Diagnostic::warning(
+                "updateRemoteUserUID",
+                "updateRemoteUserUID is enabled but no remoteUser is set, so it has no effect",
+            ));
+        }
+    }
+
+    /// Every concrete local port in `forwardPorts` and `appPort`, deduplicated and sorted
+    ///
+    /// Ranges are expanded; service-bound ports (e.g., "db:5432") belong to another
+    /// container and are skipped.
+    pub fn all_ports(&self) -> impl Iterator<Item = u16> {
+        let ports: BTreeSet<u16> = self
+            .forward_ports
+            .iter()
+            .flatten()
+            .chain(self.app_port.iter().flat_map(AppPort::ports))
+            .filter_map(PortSpec::local_ports)
+            .flatten()
+            .collect();
+        ports.into_iter()
+    }
+
+    /// Local ports listed in both `forwardPorts` and `appPort`, in ascending order
+    ///
+    /// Ports covered by ranges count, so `"3000-3005"` in `forwardPorts` makes an
+    /// `appPort` of `3001` redundant.
+    pub fn redundant_app_ports(&self) -> Vec<u16> {
+        let collect = |ports: &mut dyn Iterator<Item = &PortSpec>| -> BTreeSet<u16> {
+            ports.filter_map(PortSpec::local_ports).flatten().collect()
+        };
+        let forwarded = collect(&mut self.forward_ports.iter().flatten());
+        let app = collect(&mut self.app_port.iter().flat_map(AppPort::ports));
+        forwarded.intersection(&app).copied().collect()
+    }
+
+    /// Copy of the configuration with variables substituted from `ctx` in every string
+    ///
+    /// This covers free-form values (names, paths, env values, commands, mounts, run
+    /// args and the string leaves of `features` and `customizations`) but not map keys,
+    /// port specifications or fields captured by `allow-unknown-fields`.
+    ///
+    /// `${containerWorkspaceFolder}` resolves to `workspaceFolder` when it is set and to
+    /// [`SubstitutionContext::container_workspace_folder`] otherwise.
+    pub fn substituted(&self, ctx: &SubstitutionContext) -> DevContainer {
+        let ctx = self.substitution_context(ctx);
+        let mut devcontainer = self.clone();
+        devcontainer.walk_strings_mut(&mut |value| *value = ctx.substitute(value));
+        devcontainer
+    }
+
+    /// `ctx` with `container_workspace_folder` taken from `workspaceFolder`, if set
+    fn substitution_context(&self, ctx: &SubstitutionContext) -> SubstitutionContext {
+        let mut ctx = ctx.clone();
+        if let Some(workspace_folder) = &self.workspace_folder {
+            ctx.container_workspace_folder = Some(ctx.substitute(workspace_folder));
+        }
+        ctx
+    }
+
+    /// Distinct `${...}` variables referenced by the configuration
+    ///
+    /// Covers the same strings as [`Self::substituted`]. Names are given without the
+    /// braces and, for `localEnv`/`containerEnv`, without a default (e.g. `localEnv:FOO`).
+    pub fn collect_variables(&self) -> BTreeSet<String> {
+        let mut variables = BTreeSet::new();
+        self.visit_strings(|value| {
+            variables.extend(substitution_variables(value).into_iter().map(String::from));
+        });
+        variables
+    }
+
+    /// Call `f` with every free-form string in the configuration
+    ///
+    /// Visits the same strings as [`Self::visit_strings_mut`], without needing a
+    /// mutable (or cloned) configuration.
+    pub fn visit_strings(&self, mut f: impl FnMut(&str)) {
+        self.walk_strings(&mut |value| f(value));
+    }
+
+    /// Apply `f` to every free-form string in the configuration
+    ///
+    /// Visits the same strings that [`Self::substituted`] rewrites, so custom
+    /// substitution or linting can reuse the built-in traversal.
+    pub fn visit_strings_mut(&mut self, mut f: impl FnMut(&mut String)) {
+        self.walk_strings_mut(&mut f);
+    }
+
+    /// Apply `f` to every free-form string in the configuration (see [`Self::substituted`])
+    fn walk_strings_mut(&mut self, f: &mut dyn FnMut(&mut String)) {
+        walk_strings_body!(self, f, iter_mut, values_mut, walk_json_strings_mut)
+    }
+
+    /// Read-only counterpart of [`Self::walk_strings_mut`]
+    fn walk_strings(&self, f: &mut dyn FnMut(&String)) {
+        walk_strings_body!(self, f, iter, values, walk_json_strings)
+    }
+
+    /// `name` with variables substituted from `ctx`
+    pub fn resolved_name(&self, ctx: &SubstitutionContext) -> Option<String> {
+        let ctx = self.substitution_context(ctx);
+        self.name.as_deref().map(|name| ctx.substitute(name))
+    }
+
+    /// `build.args` with variables substituted from `ctx`
+    ///
+    /// Returns `None` when no build args are configured.
+    pub fn resolved_build_args(
+        &self,
+        ctx: &SubstitutionContext,
+    ) -> Option<BTreeMap<String, String>> {
+        let args = self.build.as_ref()?.args.as_ref()?;
+        let ctx = self.substitution_context(ctx);
+        Some(
+            args.iter()
+                .map(|(key, value)| (key.clone(), ctx.substitute(value)))
+                .collect(),
+        )
+    }
+
+    /// Builder starting from this configuration
+    pub fn into_builder(self) -> DevContainerBuilder {
+        DevContainerBuilder { devcontainer: self }
+    }
+
+    /// Builder starting from a copy of this configuration
+    pub fn to_builder(&self) -> DevContainerBuilder {
+        self.clone().into_builder()
+    }
+
+    /// Serialize to a JSON string in the style selected by `opts`
+    ///
+    /// With the default options this matches plain `serde_json` serialization.
+    pub fn to_json_with(&self, opts: &SerializeOptions) -> String {
+        let mut devcontainer = self.clone();
+        match opts.dockerfile {
+            DockerfileStyle::Preserve => {}
+            DockerfileStyle::Build => {
+                if let Some(docker_file) = devcontainer.docker_file.take() {
+                    devcontainer
+                        .build
+                        .get_or_insert_with(BuildConfig::default)
+                        .dockerfile
+                        .get_or_insert(docker_file);
+                }
+            }
+            DockerfileStyle::TopLevel => {
+                if let Some(build) = &mut devcontainer.build {
+                    if let Some(dockerfile) = build.dockerfile.take() {
+                        devcontainer.docker_file = Some(dockerfile);
+                    }
+                    if *build == BuildConfig::default() {
+                        devcontainer.build = None;
+                    }
+                }
+            }
+        }
+        #[cfg(feature = "vscode")]
+        match opts.naming {
+            PropertyNaming::Preserve => {}
+            PropertyNaming::Current => devcontainer.move_vscode_legacy_to_customizations(),
+            PropertyNaming::Historical => devcontainer.move_vscode_customizations_to_legacy(),
+        }
+        serde_json::to_string(&devcontainer).expect("DevContainer serializes to JSON")
+    }
+
+    /// Move top-level `extensions` and `settings` into `customizations.vscode`
+    ///
+    /// Values are left in place if the existing entry has a different JSON type.
+    #[cfg(feature = "vscode")]
+    fn move_vscode_legacy_to_customizations(&mut self) {
+        if self.extensions.is_none() && self.settings.is_none() {
+            return;
+        }
+        let vscode = self
+            .customizations
+            .get_or_insert_with(BTreeMap::new)
+            .entry("vscode".to_string())
+            .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()));
+        let Some(vscode) = vscode.as_object_mut() else {
+            return;
+        };
+
+        if let Some(extensions) = self.extensions.take() {
+            match vscode
+                .entry("extensions")
+                .or_insert_with(|| serde_json::Value::Array(Vec::new()))
+            {
+                serde_json::Value::Array(existing) => {
+                    let mut merged = Vec::new();
+                    let legacy = extensions.into_iter().map(serde_json::Value::String);
+                    for extension in legacy.chain(existing.drain(..)) {
+                        if !merged.contains(&extension) {
+                            merged.push(extension);
+                        }
+                    }
+                    *existing = merged;
+                }
+                _ => self.extensions = Some(extensions),
+            }
+        }
+        if let Some(settings) = self.settings.take() {
+            match vscode
+                .entry("settings")
+                .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()))
+            {
+                serde_json::Value::Object(existing) => {
+                    for (key, value) in settings {
+                        existing.entry(key).or_insert(value);
+                    }
+                }
+                _ => self.settings = Some(settings),
+            }
+        }
+    }
+
+    /// Move `customizations.vscode.extensions` and `.settings` to the top level
+    ///
+    /// Entries that don't have the shape of the top-level properties are left in place.
+    #[cfg(feature = "vscode")]
+    fn move_vscode_customizations_to_legacy(&mut self) {
+        let Some(vscode) = self
+            .customizations
+            .as_mut()
+            .and_then(|customizations| customizations.get_mut("vscode"))
+            .and_then(serde_json::Value::as_object_mut)
+        else {
+            return;
+        };
+
+        let extensions = vscode
+            .get("extensions")
+            .and_then(|extensions| Vec::<String>::deserialize(extensions).ok());
+        if let Some(extensions) = extensions {
+            vscode.remove("extensions");
+            let legacy = self.extensions.get_or_insert_with(Vec::new);
+            for extension in extensions {
+                if !legacy.contains(&extension) {
+                    legacy.push(extension);
+                }
+            }
+        }
+        if vscode
+            .get("settings")
+            .is_some_and(serde_json::Value::is_object)
+        {
+            if let Some(serde_json::Value::Object(settings)) = vscode.remove("settings") {
+                self.settings
+                    .get_or_insert_with(BTreeMap::new)
+                    .extend(settings);
+            }
+        }
+
+        if vscode.is_empty() {
+            self.remove_customization("vscode");
+        }
+    }
+
+    /// Serialize to a JSON string, additionally omitting empty collections
+    ///
+    /// Plain `serde_json` serialization only skips `None` fields, so an explicitly empty
+    /// `Some(vec![])` or `Some(BTreeMap::new())` still renders as `[]` or `{}`. This
+    /// drops such properties from the top level and from `build`. Nested values are left
+    /// alone, so a feature with empty options (`"...:1": {}`) is kept.
+    ///
+    /// Properties are written in key order. `containerEnv` and `remoteEnv` keep the
+    /// order of their map, so with `ordered-env` they stay in file order.
+    pub fn to_minimal_json(&self) -> String {
+        let mut value = serde_json::to_value(self).expect("DevContainer serializes to JSON");
+        let serde_json::Value::Object(properties) = &mut value else {
+            return value.to_string();
+        };
+        if let Some(serde_json::Value::Object(build)) = properties.get_mut("build") {
+            build.retain(|_, value| !is_empty_collection(value));
+        }
+        properties.retain(|_, value| !is_empty_collection(value));
+        let properties = EnvOrderedProperties {
+            properties,
+            devcontainer: self,
+        };
+        serde_json::to_string(&properties).expect("DevContainer serializes to JSON")
+    }
+
+    /// Typed `customizations.vscode` block, if present
+    pub fn vscode_customizations(&self) -> Option<Result<VsCodeCustomizations, serde_json::Error>> {
+        self.customization("vscode")
+    }
+
+    /// Extensions from the legacy top-level `extensions` and `customizations.vscode.extensions`
+    ///
+    /// Legacy entries come first; later duplicates are dropped. A malformed
+    /// `customizations.vscode` block contributes nothing.
+    #[cfg(feature = "vscode")]
+    pub fn all_extensions(&self) -> Vec<String> {
+        let customized = self
+            .vscode_customizations()
+            .and_then(Result::ok)
+            .and_then(|vscode| vscode.extensions);
+        let mut extensions: Vec<String> = Vec::new();
+        for extension in self.extensions.iter().chain(customized.iter()).flatten() {
+            if !extensions.contains(extension) {
+                extensions.push(extension.clone());
+            }
+        }
+        extensions
+    }
+
+    /// All known customization namespaces, each deserialized once
+    ///
+    /// A namespace that is absent or doesn't match its typed shape is `None`; use the
+    /// per-namespace accessor (e.g. [`Self::vscode_customizations`]) to see the error.
+    pub fn customizations_typed(&self) -> TypedCustomizations {
+        TypedCustomizations {
+            vscode: self.customization("vscode").and_then(Result::ok),
+            jetbrains: self.customization("jetbrains").and_then(Result::ok),
+            codespaces: self.customization("codespaces").and_then(Result::ok),
+        }
+    }
+
+    /// Set the customization block for `namespace`, replacing any existing one
+    pub fn set_customization(&mut self, namespace: &str, value: serde_json::Value) {
+        self.customizations
+            .get_or_insert_with(BTreeMap::new)
+            .insert(namespace.to_string(), value);
+    }
+
+    /// Remove and return the customization block for `namespace`
+    ///
+    /// `customizations` is reset to `None` once its last namespace is removed.
+    pub fn remove_customization(&mut self, namespace: &str) -> Option<serde_json::Value> {
+        let customizations = self.customizations.as_mut()?;
+        let removed = customizations.remove(namespace);
+        if customizations.is_empty() {
+            self.customizations = None;
+        }
+        removed
+    }
+
+    /// Keys from `required` that are missing from the `namespace` customization block
+    ///
+    /// An absent or non-object block is missing every required key. Keys are returned in
+    /// the order given.
+    pub fn validate_customization_required_keys(
+        &self,
+        namespace: &str,
+        required: &[&str],
+    ) -> Vec<String> {
+        let block = self
+            .customizations
+            .as_ref()
+            .and_then(|customizations| customizations.get(namespace))
+            .and_then(serde_json::Value::as_object);
+        required
+            .iter()
+            .filter(|key| block.is_none_or(|block| !block.contains_key(**key)))
+            .map(|key| key.to_string())
+            .collect()
+    }
+
+    fn customization<T: serde::de::DeserializeOwned>(
+        &self,
+        namespace: &str,
+    ) -> Option<Result<T, serde_json::Error>> {
+        let value = self.customizations.as_ref()?.get(namespace)?;
+        Some(T::deserialize(value))
+    }
+
+    /// `build.dockerfile` and the top-level `dockerFile` disagree
+    fn lint_dockerfile_conflict(&self, diagnostics: &mut Vec<Diagnostic>) {
+        let build_dockerfile = self
+            .build
+            .as_ref()
+            .and_then(|build| build.dockerfile.as_ref());
+        if let (Some(build_dockerfile), Some(docker_file)) = (build_dockerfile, &self.docker_file) {
+            if build_dockerfile != docker_file {
+                diagnostics.push(Diagnostic::warning(
+                    "dockerFile",
+                    alloc::format!(
+                        "dockerFile ({}) conflicts with build.dockerfile ({}); build.dockerfile takes precedence",
+                        docker_file, build_dockerfile
+                    ),
+                ));
+            }
+        }
+    }
+
+    /// `waitFor` names a lifecycle command that isn't configured
+    ///
+    /// An unknown name is reported too. `initializeCommand` runs on the host and isn't
+    /// modelled by this crate, so it isn't checked.
+    fn lint_wait_for(&self, diagnostics: &mut Vec<Diagnostic>) {
+        match &self.wait_for {
+            Some(WaitFor::Phase(phase)) if self.command_for_phase(*phase).is_none() => {
+                diagnostics.push(Diagnostic::warning(
+                    "waitFor",
+                    alloc::format!(
+                        "waitFor refers to {}, which is not configured",
+                        phase.as_str()
+                    ),
+                ));
+            }
+            Some(WaitFor::Other(name)) => {
+                diagnostics.push(Diagnostic::warning(
+                    "waitFor",
+                    alloc::format!("waitFor refers to unknown lifecycle command {:?}", name),
+                ));
+            }
+            _ => {}
+        }
+    }
+
+    /// Mount targets must be absolute container paths
+    ///
+    /// Targets starting with a `${...}` variable are assumed to resolve to one.
+    fn lint_mount_targets(&self, diagnostics: &mut Vec<Diagnostic>) {
+        for mount in self.mounts.iter().flatten() {
+            let Some(target) = mount.to_spec().and_then(|spec| spec.target) else {
+                continue;
+            };
+            if !target.starts_with('/') && !target.starts_with("${") {
+                diagnostics.push(Diagnostic::error(
+                    "mounts",
+                    alloc::format!("mount target {:?} is not an absolute path", target),
+                ));
+            }
+        }
+    }
+
+    /// `overrideFeatureInstallOrder` entries must name distinct, declared features
+    ///
+    /// Entries and feature keys are compared by [`FeatureRef::id`], so an entry may
+    /// omit the version.
+    fn lint_feature_install_order(&self, diagnostics: &mut Vec<Diagnostic>) {
+        let Some(order) = &self.override_feature_install_order else {
+            return;
+        };
+        let declared: BTreeSet<String> = self
+            .features
+            .iter()
+            .flat_map(|features| features.keys())
+            .map(|key| feature_identity(key))
+            .collect();
+
+        let mut seen = BTreeSet::new();
+        for entry in order {
+            let identity = feature_identity(entry);
+            if !declared.contains(&identity) {
+                diagnostics.push(Diagnostic::error(
+                    "overrideFeatureInstallOrder",
+                    alloc::format!("{} is not declared in features", entry),
+                ));
+            }
+            if !seen.insert(identity) {
+                diagnostics.push(Diagnostic::error(
+                    "overrideFeatureInstallOrder",
+                    alloc::format!("{} is listed more than once", entry),
+                ));
+            }
+        }
+    }
+
+    /// Dockerfile to build, with `build.dockerfile` taking precedence over `dockerFile`
+    pub fn effective_dockerfile(&self) -> Option<&str> {
+        self.build
+            .as_ref()
+            .and_then(|build| build.dockerfile.as_deref())
+            .or(self.docker_file.as_deref())
+    }
+
+    /// `portsAttributes` keys that are neither a port, a port range nor a pattern
+    ///
+    /// A key counts as a pattern (matched against process command lines) when it
+    /// contains a glob or regex metacharacter, so `"abc"` and `"3000-"` are reported
+    /// while `"40000-55000"` and `".+/server.js"` are not.
+    pub fn invalid_port_attribute_keys(&self) -> Vec<String> {
+        self.ports_attributes
+            .iter()
+            .flat_map(|attributes| attributes.keys())
+            .filter(|key| PortAttributeKey::parse(key).is_none())
+            .cloned()
+            .collect()
+    }
+
+    /// Each single forwarded port with the auto-forward action that applies to it
+    ///
+    /// Ports are listed once, in `forwardPorts` order. The action comes from the
+    /// `portsAttributes` entry for the port (an exact key wins over a range), then
+    /// `otherPortsAttributes`, then [`OnAutoForward::Notify`].
+    pub fn auto_forward_summary(&self) -> Vec<(u16, OnAutoForward)> {
+        let mut summary: Vec<(u16, OnAutoForward)> = Vec::new();
+        for port in self
+            .forward_ports
+            .iter()
+            .flatten()
+            .filter_map(PortSpec::as_number)
+        {
+            if summary.iter().any(|(seen, _)| *seen == port) {
+                continue;
+            }
+            let action = self
+                .port_attributes_for(port)
+                .and_then(|attributes| attributes.on_auto_forward.clone())
+                .or_else(|| {
+                    self.other_ports_attributes
+                        .as_ref()
+                        .and_then(|attributes| attributes.on_auto_forward.clone())
+                })
+                .unwrap_or_default();
+            summary.push((port, action));
+        }
+        summary
+    }
+
+    /// `portsAttributes` entry for a port: its exact key, else the first matching range
+    fn port_attributes_for(&self, port: u16) -> Option<&PortAttributes> {
+        let attributes = self.ports_attributes.as_ref()?;
+        let mut range_match = None;
+        for (key, value) in attributes {
+            match PortAttributeKey::parse(key) {
+                Some(PortAttributeKey::Port(p)) if p == port => return Some(value),
+                Some(PortAttributeKey::Range(range)) if range.contains(port) => {
+                    range_match.get_or_insert(value);
+                }
+                _ => {}
+            }
+        }
+        range_match
+    }
+
+    /// Overlay `env` onto `containerEnv`, overwriting existing keys
+    ///
+    /// Unlike [`Self::merge`], this only touches `containerEnv`, creating it if needed.
+    pub fn with_overlay_env(&mut self, env: BTreeMap<String, String>) -> &mut Self {
+        self.container_env
+            .get_or_insert_with(EnvMap::default)
+            .extend(env);
+        self
+    }
+
+    /// Keys set in both `containerEnv` and `remoteEnv`, in key order
+    ///
+    /// The overlap is usually intentional (`remoteEnv` wins in tool processes), but is
+    /// worth showing when displaying diagnostics.
+    pub fn env_overlaps(&self) -> Vec<String> {
+        let (Some(container_env), Some(remote_env)) = (&self.container_env, &self.remote_env)
+        else {
+            return Vec::new();
+        };
+        container_env
+            .keys()
+            .filter(|key| remote_env.contains_key(*key))
+            .cloned()
+            .collect()
+    }
+
+    /// Total size in bytes of `containerEnv` and `remoteEnv` rendered as `KEY=VALUE`
+    pub fn env_payload_bytes(&self) -> usize {
+        [&self.container_env, &self.remote_env]
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|(key, value)| key.len() + 1 + value.len())
+            .sum()
+    }
+
+    /// Error diagnostic if the env payload exceeds `limit` bytes
+    ///
+    /// The limit depends on the target platform, so this check is not part of [`Self::lint`].
+    pub fn check_env_payload(&self, limit: usize) -> Option<Diagnostic> {
+        let size = self.env_payload_bytes();
+        (size > limit).then(|| {
+            Diagnostic::error(
+                "containerEnv",
+                alloc::format!(
+                    "environment is {} bytes, exceeding the limit of {} bytes",
+                    size,
+                    limit
+                ),
+            )
+        })
+    }
+
+    /// Copy of the configuration that is safe to log
+    ///
+    /// `containerEnv` and `remoteEnv` values whose key contains one of
+    /// [`SENSITIVE_ENV_KEY_PATTERNS`] are replaced with [`REDACTED`].
+    pub fn sanitize_for_logging(&self) -> DevContainer {
+        let mut devcontainer = self.clone();
+        devcontainer.redact_env(SENSITIVE_ENV_KEY_PATTERNS);
+        devcontainer
+    }
+
+    /// JSON value of the configuration suitable for telemetry
+    ///
+    /// Like [`Self::sanitize_for_logging`], but env values are redacted when their key
+    /// contains any of `patterns` (ignoring case). `secrets` only carries descriptions,
+    /// never values, so it is kept as is.
+    ///
+    /// A `serde_json::Value` object is sorted by key, so env maps lose their file order
+    /// here even with `ordered-env`; serialize [`Self::sanitize_for_logging`] to keep it.
+    pub fn to_json_value_redacted(&self, patterns: &[&str]) -> serde_json::Value {
+        let mut devcontainer = self.clone();
+        devcontainer.redact_env(patterns);
+        serde_json::to_value(devcontainer).expect("DevContainer serializes to JSON")
+    }
+
+    /// Redact env values whose key contains any of `patterns`, ignoring case
+    fn redact_env(&mut self, patterns: &[&str]) {
+        let is_sensitive = |key: &str| {
+            let key = key.to_ascii_uppercase();
+            patterns
+                .iter()
+                .any(|pattern| key.contains(&pattern.to_ascii_uppercase()))
+        };
+        for env in [&mut self.container_env, &mut self.remote_env] {
+            for (key, value) in env.iter_mut().flatten() {
+                if is_sensitive(key) {
+                    *value = REDACTED.to_string();
+                }
+            }
+        }
+    }
+
+    /// Whether the configuration is compose-based (both `dockerComposeFile` and `service`)
+    ///
+    /// Always `false` without the `docker-compose` feature.
+    pub fn requires_docker_compose(&self) -> bool {
+        #[cfg(feature = "docker-compose")]
+        if self.docker_compose_file.is_some() && self.service.is_some() {
+            return true;
+        }
+        false
+    }
+
+    /// Compose services named by `service` and by `service:port` entries in `forwardPorts`
+    ///
+    /// `service` comes first, then forwarded services in order; each name is listed once.
+    #[cfg(feature = "docker-compose")]
+    pub fn referenced_services(&self) -> Vec<&str> {
+        let forwarded = self
+            .forward_ports
+            .iter()
+            .flatten()
+            .filter_map(|port| match port {
+                PortSpec::Service(service) => Some(service.service.as_str()),
+                _ => None,
+            });
+        let mut services: Vec<&str> = Vec::new();
+        for service in self.service.as_deref().into_iter().chain(forwarded) {
+            if !services.contains(&service) {
+                services.push(service);
+            }
+        }
+        services
+    }
+
+    /// `docker-compose.override.yml` fragment for the configured `service`
+    ///
+    /// The result has the shape `{"services": {"<service>": {...}}}` with `environment`
+    /// taken from `containerEnv`, `ports` from the local ports of `forwardPorts` and
+    /// `appPort` and `user` from `containerUser`; empty entries are omitted. Returns
+    /// `None` when no `service` is set.
+    ///
+    /// Ports are published as `"N:N"`, and ranges in compose range syntax
+    /// (`"S-E:S-E"`) rather than one entry per port. Overlapping entries are merged as
+    /// in [`Self::normalize_forward_ports`].
+    ///
+    /// `environment` is a mapping, except with `ordered-env`, where it uses compose's
+    /// list form (`["KEY=value", ...]`): a `serde_json::Map` is sorted by key, so only
+    /// a list keeps the file order.
+    #[cfg(feature = "docker-compose")]
+    pub fn to_compose_override(&self) -> Option<serde_json::Value> {
+        let service = self.service.as_ref()?;
+
+        let mut definition = serde_json::Map::new();
+        if let Some(env) = self.container_env.as_ref().filter(|env| !env.is_empty()) {
+            #[cfg(not(feature = "ordered-env"))]
+            let environment = serde_json::to_value(env).expect("env map serializes to JSON");
+            #[cfg(feature = "ordered-env")]
+            let environment = serde_json::Value::Array(
+                env.iter()
+                    .map(|(key, value)| {
+                        serde_json::Value::String(alloc::format!("{}={}", key, value))
+                    })
+                    .collect(),
+            );
+            definition.insert("environment".to_string(), environment);
+        }
+        let ranges = self
+            .forward_ports
+            .iter()
+            .flatten()
+            .chain(self.app_port.iter().flat_map(AppPort::ports))
+            .filter_map(PortSpec::local_ports)
+            .map(|ports| (*ports.start(), *ports.end()))
+            .collect();
+        let ports: Vec<serde_json::Value> = merge_port_ranges(ranges)
+            .into_iter()
+            .map(|(start, end)| {
+                let published = if start == end {
+                    alloc::format!("{}:{}", start, start)
+                } else {
+                    alloc::format!("{}-{}:{}-{}", start, end, start, end)
+                };
+                serde_json::Value::String(published)
+            })
+            .collect();
+        if !ports.is_empty() {
+            definition.insert("ports".to_string(), serde_json::Value::Array(ports));
+        }
+        if let Some(user) = &self.container_user {
+            definition.insert("user".to_string(), serde_json::Value::String(user.clone()));
+        }
+
+        let mut services = serde_json::Map::new();
+        services.insert(service.clone(), serde_json::Value::Object(definition));
+        let mut document = serde_json::Map::new();
+        document.insert("services".to_string(), serde_json::Value::Object(services));
+        Some(serde_json::Value::Object(document))
+    }
+
+    /// Parsed `image` reference, if `image` is set and well-formed
+    pub fn image_ref(&self) -> Option<ImageRef> {
+        self.image.as_deref().and_then(ImageRef::parse)
+    }
+
+    /// Number of configured lifecycle commands, counting each entry of a parallel object
+    pub fn command_count(&self) -> usize {
+        self.lifecycle_commands()
+            .map(|(_, command)| match command {
+                LifecycleCommand::Command(_) => 1,
+                LifecycleCommand::Object(commands) => commands.len(),
+            })
+            .sum()
+    }
+
+    /// Configured lifecycle commands in phase order
+    fn lifecycle_commands(&self) -> impl Iterator<Item = (LifecyclePhase, &LifecycleCommand)> {
+        LifecyclePhase::ALL.iter().filter_map(|&phase| {
+            self.command_for_phase(phase)
+                .map(|command| (phase, command))
+        })
+    }
+
+    /// Command configured for `phase`, if any
+    pub fn command_for_phase(&self, phase: LifecyclePhase) -> Option<&LifecycleCommand> {
+        match phase {
+            LifecyclePhase::OnCreate => self.on_create_command.as_ref(),
+            LifecyclePhase::UpdateContent => self.update_content_command.as_ref(),
+            LifecyclePhase::PostCreate => self.post_create_command.as_ref(),
+            LifecyclePhase::PostStart => self.post_start_command.as_ref(),
+            LifecyclePhase::PostAttach => self.post_attach_command.as_ref(),
+        }
+    }
+}
+
+/// Privilege-escalating settings of a configuration, for security review
+///
+/// Returned by [`DevContainer::count_privileged_capabilities`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub struct PrivilegeReport {
+    /// Whether `privileged` is set to `true`
+    pub privileged: bool,
+    /// Number of `capAdd` entries
+    pub cap_add: usize,
+    /// Number of `securityOpt` entries
+    pub security_opt: usize,
+}
+
+impl core::str::FromStr for DevContainer {
+    type Err = ParseError;
+
+    /// Parse a devcontainer.json document, ignoring a leading UTF-8 byte order mark
+    /// and whitespace
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let devcontainer = serde_json::from_str(trim_preamble(s))?;
+        Ok(DevContainer::parsed(devcontainer))
+    }
+}
+
+/// UTF-8 byte order mark some editors write at the start of a file
+const UTF8_BOM: &str = "\u{FEFF}";
+
+/// `input` without a leading byte order mark and whitespace
+fn trim_preamble(input: &str) -> &str {
+    input.strip_prefix(UTF8_BOM).unwrap_or(input).trim_start()
+}
+
+/// Fluent builder for a [`DevContainer`]
+///
+/// Start from [`DevContainerBuilder::new`] or from an existing configuration with
+/// [`DevContainer::into_builder`]. Fields without a setter can be changed on the
+/// configuration returned by [`DevContainerBuilder::build`].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DevContainerBuilder {
+    devcontainer: DevContainer,
+}
+
+impl DevContainerBuilder {
+    /// Builder for an empty configuration
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set `name`
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.devcontainer.name = Some(name.into());
+        self
+    }
+
+    /// Set `image`
+    pub fn image(mut self, image: impl Into<String>) -> Self {
+        self.devcontainer.image = Some(image.into());
+        self
+    }
+
+    /// Set `build.dockerfile`, keeping any other build settings
+    pub fn dockerfile(mut self, dockerfile: impl Into<String>) -> Self {
+        self.devcontainer
+            .build
+            .get_or_insert_with(BuildConfig::default)
+            .dockerfile = Some(dockerfile.into());
+        self
+    }
+
+    /// Declare the feature `id` with `options`, replacing earlier options for `id`
+    pub fn feature(mut self, id: impl Into<String>, options: serde_json::Value) -> Self {
+        self.devcontainer
+            .features
+            .get_or_insert_with(BTreeMap::new)
+            .insert(id.into(), options);
+        self
+    }
+
+    /// Append an entry to `forwardPorts`
+    pub fn forward_port(mut self, port: PortSpec) -> Self {
+        self.devcontainer
+            .forward_ports
+            .get_or_insert_with(Vec::new)
+            .push(port);
+        self
+    }
+
+    /// Set the `containerEnv` variable `key`
+    pub fn container_env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.devcontainer
+            .container_env
+            .get_or_insert_with(EnvMap::default)
+            .insert(key.into(), value.into());
+        self
+    }
+
+    /// Set `remoteUser`
+    pub fn remote_user(mut self, user: impl Into<String>) -> Self {
+        self.devcontainer.remote_user = Some(user.into());
+        self
+    }
+
+    /// Set `workspaceFolder`
+    pub fn workspace_folder(mut self, folder: impl Into<String>) -> Self {
+        self.devcontainer.workspace_folder = Some(folder.into());
+        self
+    }
+
+    /// Set `postCreateCommand`
+    pub fn post_create_command(mut self, command: LifecycleCommand) -> Self {
+        self.devcontainer.post_create_command = Some(command);
+        self
+    }
+
+    /// Finish building the configuration
+    pub fn build(self) -> DevContainer {
+        self.devcontainer
+    }
+}
+
+/// Options for [`DevContainer::to_json_with`]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub struct SerializeOptions {
+    /// Where the Dockerfile path is written
+    pub dockerfile: DockerfileStyle,
+
+    /// Whether VS Code extensions and settings use the current or historical properties
+    #[cfg(feature = "vscode")]
+    pub naming: PropertyNaming,
+}
+
+/// Which properties [`DevContainer::to_json_with`] uses for VS Code extensions and settings
+///
+/// Before `customizations` existed, VS Code read `extensions` and `settings` from the
+/// top level of devcontainer.json; the spec now nests them under `customizations.vscode`.
+#[cfg(feature = "vscode")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum PropertyNaming {
+    /// Keep both spellings as they are
+    #[default]
+    Preserve,
+    /// Move top-level `extensions` and `settings` into `customizations.vscode`
+    ///
+    /// Extensions are listed as in [`DevContainer::all_extensions`]; on a settings key
+    /// clash the `customizations.vscode` value wins.
+    Current,
+    /// Move `customizations.vscode.extensions` and `.settings` to the historical
+    /// top-level `extensions` and `settings`
+    ///
+    /// Extensions and settings are combined as for [`PropertyNaming::Current`], and
+    /// the `vscode` block is dropped if nothing else is left in it.
+    Historical,
+}
+
+/// Where [`DevContainer::to_json_with`] writes the Dockerfile path
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum DockerfileStyle {
+    /// Keep `dockerFile` and `build.dockerfile` as they are
+    #[default]
+    Preserve,
+    /// Move a top-level `dockerFile` into `build.dockerfile` (the current spec form)
+    ///
+    /// If both are set, `build.dockerfile` wins, as in [`DevContainer::effective_dockerfile`].
+    Build,
+    /// Move `build.dockerfile` to the historical top-level `dockerFile`
+    ///
+    /// `build` is dropped if nothing else is left in it.
+    TopLevel,
+}
+
+/// Build configuration for the dev container
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[non_exhaustive]
+#[cfg_attr(not(feature = "allow-unknown-fields"), serde(deny_unknown_fields))]
+pub struct BuildConfig {
+    /// Path to Dockerfile
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dockerfile: Option<String>,
+
+    /// Build context path
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+
+    /// Build arguments
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub args: Option<BTreeMap<String, String>>,
+
+    /// Target stage in multi-stage build
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+
+    /// Images to use as build cache (a single image is accepted in place of an array)
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        rename = "cacheFrom",
+        deserialize_with = "one_or_many"
+    )]
+    pub cache_from: Option<Vec<String>>,
+
+    /// Labels to apply to the built image
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub labels: Option<BTreeMap<String, String>>,
+
+    /// Network to use for `RUN` instructions during the build
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub network: Option<String>,
+
+    /// Additional unknown fields when allow-unknown-fields feature is enabled
+    #[cfg(feature = "allow-unknown-fields")]
+    #[serde(flatten)]
+    pub additional_fields: BTreeMap<String, serde_json::Value>,
+}
+
+impl BuildConfig {
+    /// Default build context, relative to the folder containing devcontainer.json
+    pub const DEFAULT_CONTEXT: &'static str = ".";
+
+    /// Configured build context, or [`Self::DEFAULT_CONTEXT`] if absent
+    pub fn effective_context(&self) -> &str {
+        self.context.as_deref().unwrap_or(Self::DEFAULT_CONTEXT)
+    }
+
+    /// Merge `other` on top of this build configuration (see [`DevContainer::merge`])
+    fn merge(&mut self, other: &BuildConfig) {
+        merge_replace(&mut self.dockerfile, &other.dockerfile);
+        merge_replace(&mut self.context, &other.context);
+        merge_keys(&mut self.args, &other.args);
+        merge_replace(&mut self.target, &other.target);
+        merge_replace(&mut self.cache_from, &other.cache_from);
+        merge_keys(&mut self.labels, &other.labels);
+        merge_replace(&mut self.network, &other.network);
+        #[cfg(feature = "allow-unknown-fields")]
+        self.additional_fields.extend(
+            other
+                .additional_fields
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone())),
+        );
+    }
+}
+
+/// Typed view of the known `customizations` namespaces
+#[derive(Debug, Clone, PartialEq, Default)]
+#[non_exhaustive]
+pub struct TypedCustomizations {
+    /// `customizations.vscode`
+    pub vscode: Option<VsCodeCustomizations>,
+    /// `customizations.jetbrains`
+    pub jetbrains: Option<JetBrainsCustomizations>,
+    /// `customizations.codespaces`
+    pub codespaces: Option<CodespacesCustomizations>,
+}
+
+/// VS Code customizations (`customizations.vscode`)
+///
+/// Customization blocks belong to their tools, so properties not modelled here are
+/// always kept in `additional_fields` rather than rejected.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[non_exhaustive]
+pub struct VsCodeCustomizations {
+    /// Extensions to install
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extensions: Option<Vec<String>>,
+
+    /// Settings to apply in the container
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub settings: Option<BTreeMap<String, serde_json::Value>>,
+
+    /// Other properties defined by the tool
+    #[serde(flatten)]
+    pub additional_fields: BTreeMap<String, serde_json::Value>,
+}
+
+impl VsCodeCustomizations {
+    /// Extension IDs listed more than once, ignoring case as VS Code does
+    ///
+    /// Each duplicate is reported once, spelled as its first occurrence.
+    pub fn duplicate_extensions(&self) -> Vec<String> {
+        let mut first_seen: BTreeMap<String, &String> = BTreeMap::new();
+        let mut reported = BTreeSet::new();
+        let mut duplicates = Vec::new();
+        for extension in self.extensions.iter().flatten() {
+            let id = extension.to_ascii_lowercase();
+            match first_seen.get(&id) {
+                Some(first) if reported.insert(id.clone()) => duplicates.push((*first).clone()),
+                Some(_) => {}
+                None => {
+                    first_seen.insert(id, extension);
+                }
+            }
+        }
+        duplicates
+    }
+}
+
+/// JetBrains customizations (`customizations.jetbrains`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[non_exhaustive]
+pub struct JetBrainsCustomizations {
+    /// IDE backend to use (e.g., "IntelliJ")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backend: Option<String>,
+
+    /// Plugins to install
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plugins: Option<Vec<String>>,
+
+    /// Other properties defined by the tool
+    #[serde(flatten)]
+    pub additional_fields: BTreeMap<String, serde_json::Value>,
+}
+
+/// GitHub Codespaces customizations (`customizations.codespaces`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[non_exhaustive]
+pub struct CodespacesCustomizations {
+    /// Additional repository permissions
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repositories: Option<BTreeMap<String, serde_json::Value>>,
+
+    /// Files to open when the codespace starts
+    #[serde(skip_serializing_if = "Option::is_none", rename = "openFiles")]
+    pub open_files: Option<Vec<String>>,
+
+    /// Other properties defined by the tool
+    #[serde(flatten)]
+    pub additional_fields: BTreeMap<String, serde_json::Value>,
+}
+
+/// Parsed reference to a feature (a key of the `features` map)
+///
+/// Displays as a feature key equivalent to the one parsed; unlike
+/// [`FeatureRef::canonical`], no `latest` tag is added.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FeatureRef {
+    /// Feature published to an OCI registry (e.g., "ghcr.io/devcontainers/features/node:1")
+    Oci {
+        /// Registry host (e.g., "ghcr.io")
+        registry: String,
+        /// Path between the registry and the feature name (e.g., "devcontainers/features")
+        namespace: String,
+        /// Feature name
+        name: String,
+        /// Version tag, if any
+        version: Option<String>,
+        /// Content digest (e.g., "sha256:..."), if any
+        digest: Option<String>,
+    },
+    /// Feature tarball referenced by URL
+    Tarball {
+        /// Tarball URL
+        url: String,
+    },
+    /// Feature in a folder relative to devcontainer.json (e.g., "./local-features/my-feature")
+    Local {
+        /// Relative path, as written
+        path: String,
+    },
+}
+
+impl FeatureRef {
+    /// Parse a feature key
+    ///
+    /// OCI references need at least `registry/namespace/name`; the registry may carry a
+    /// port, so the tag is only looked for in the last path segment.
+    pub fn parse(s: &str) -> Option<Self> {
+        if s.starts_with("https://") || s.starts_with("http://") {
+            return Some(FeatureRef::Tarball { url: s.to_string() });
+        }
+        if s.starts_with("./") || s.starts_with("../") {
+            return Some(FeatureRef::Local {
+                path: s.to_string(),
+            });
+        }
+
+        let (path, digest) = match s.split_once('@') {
+            Some((path, digest)) if !digest.is_empty() => (path, Some(digest.to_string())),
+            Some(_) => return None,
+            None => (s, None),
+        };
+
+        let (rest, last) = path.rsplit_once('/')?;
+        let (registry, namespace) = rest.split_once('/')?;
+        let (name, version) = match last.split_once(':') {
+            Some((name, version)) if !version.is_empty() => (name, Some(version.to_string())),
+            Some(_) => return None,
+            None => (last, None),
+        };
+
+        if registry.is_empty() || name.is_empty() || namespace.split('/').any(str::is_empty) {
+            return None;
+        }
+
+        Some(FeatureRef::Oci {
+            registry: registry.to_string(),
+            namespace: namespace.to_string(),
+            name: name.to_string(),
+            version,
+            digest,
+        })
+    }
+
+    /// Stable identifier for deduplication
+    ///
+    /// A missing tag is normalized to `latest` unless the reference is pinned by digest;
+    /// explicit versions are preserved as written.
+    pub fn canonical(&self) -> String {
+        match self {
+            FeatureRef::Oci {
+                registry,
+                namespace,
+                name,
+                version,
+                digest,
+            } => {
+                let mut canonical = alloc::format!("{}/{}/{}", registry, namespace, name);
+                match (version, digest) {
+                    (Some(version), _) => {
+                        canonical.push(':');
+                        canonical.push_str(version);
+                    }
+                    (None, None) => canonical.push_str(":latest"),
+                    (None, Some(_)) => {}
+                }
+                if let Some(digest) = digest {
+                    canonical.push('@');
+                    canonical.push_str(digest);
+                }
+                canonical
+            }
+            FeatureRef::Tarball { url } => url.clone(),
+            FeatureRef::Local { path } => path.clone(),
+        }
+    }
+
+    /// Feature identifier without version or digest (e.g., "ghcr.io/devcontainers/features/node")
+    pub fn id(&self) -> String {
+        match self {
+            FeatureRef::Oci {
+                registry,
+                namespace,
+                name,
+                ..
+            } => alloc::format!("{}/{}/{}", registry, namespace, name),
+            FeatureRef::Tarball { url } => url.clone(),
+            FeatureRef::Local { path } => path.clone(),
+        }
+    }
+
+    /// Whether the pinned version is at least `wanted`
+    ///
+    /// When both versions are dotted numbers, their major and minor components are
+    /// compared numerically, limited to the components both specify: a floating `2`
+    /// satisfies `2.1`, while `2.0` does not. An untagged or `latest` reference always
+    /// satisfies, while digest-only, tarball and local references never do. Other versions
+    /// fall back to string equality.
+    pub fn version_satisfies(&self, wanted: &str) -> bool {
+        let version = match self {
+            FeatureRef::Oci {
+                version: Some(version),
+                ..
+            } => version.as_str(),
+            FeatureRef::Oci { digest: None, .. } => return true,
+            _ => return false,
+        };
+        if version == "latest" {
+            return true;
+        }
+
+        let numeric = |version: &str| -> Option<Vec<u64>> {
+            version
+                .split('.')
+                .take(2)
+                .map(|part| part.parse().ok())
+                .collect()
+        };
+        match (numeric(version), numeric(wanted)) {
+            (Some(have), Some(want)) => {
+                let len = have.len().min(want.len());
+                have[..len] >= want[..len]
+            }
+            _ => version == wanted,
+        }
+    }
+}
+
+impl core::fmt::Display for FeatureRef {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            FeatureRef::Oci {
+                registry,
+                namespace,
+                name,
+                version,
+                digest,
+            } => {
+                write!(f, "{}/{}/{}", registry, namespace, name)?;
+                if let Some(version) = version {
+                    write!(f, ":{}", version)?;
+                }
+                if let Some(digest) = digest {
+                    write!(f, "@{}", digest)?;
+                }
+                Ok(())
+            }
+            FeatureRef::Tarball { url } => f.write_str(url),
+            FeatureRef::Local { path } => f.write_str(path),
+        }
+    }
+}
+
+/// Borrowed view of a feature's options (see [`DevContainer::feature_options`])
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FeatureOptions<'a> {
+    options: &'a serde_json::Map<String, serde_json::Value>,
+}
+
+impl<'a> FeatureOptions<'a> {
+    /// Raw value of option `key`, if set
+    pub fn raw(&self, key: &str) -> Option<&'a serde_json::Value> {
+        self.options.get(key)
+    }
+
+    /// Option names, in key order
+    pub fn keys(&self) -> impl Iterator<Item = &'a String> {
+        self.options.keys()
+    }
+
+    /// Deserialize option `key`, or `None` if it isn't set
+    pub fn get<T: serde::de::DeserializeOwned>(
+        &self,
+        key: &str,
+    ) -> Option<Result<T, serde_json::Error>> {
+        self.raw(key).map(T::deserialize)
+    }
+
+    /// Option `key` as `T`, or `default` if it is absent or doesn't deserialize as `T`
+    pub fn get_or<T: serde::de::DeserializeOwned + Clone>(&self, key: &str, default: T) -> T {
+        self.get(key).and_then(Result::ok).unwrap_or(default)
+    }
+}
+
+/// Parsed container image reference (e.g., "mcr.microsoft.com/devcontainers/rust:latest")
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub struct ImageRef {
+    /// Registry host, if given; Docker Hub is implied otherwise
+    pub registry: Option<String>,
+    /// Repository path within the registry (e.g., "devcontainers/rust")
+    pub repository: String,
+    /// Tag, if given
+    pub tag: Option<String>,
+    /// Content digest (e.g., "sha256:..."), if given
+    pub digest: Option<String>,
+}
+
+impl ImageRef {
+    /// Registry used when a reference doesn't name one
+    pub const DEFAULT_REGISTRY: &'static str = "docker.io";
+
+    /// Tag used when a reference has neither a tag nor a digest
+    pub const DEFAULT_TAG: &'static str = "latest";
+
+    /// Parse an image reference of the form `[registry/]repository[:tag][@digest]`
+    ///
+    /// The first path component is taken as the registry only when it looks like a
+    /// host (contains `.` or `:`, or is `localhost`), following Docker's rules.
+    pub fn parse(s: &str) -> Option<Self> {
+        let (name, digest) = match s.split_once('@') {
+            Some((name, digest)) => (name, Some(digest)),
+            None => (s, None),
+        };
+
+        let (registry, path) = match name.split_once('/') {
+            Some((host, path))
+                if host.contains('.') || host.contains(':') || host == "localhost" =>
+            {
+                (Some(host), path)
+            }
+            _ => (None, name),
+        };
+
+        let (repository, tag) = match path.rsplit_once(':') {
+            Some((repository, tag)) if !tag.contains('/') => (repository, Some(tag)),
+            _ => (path, None),
+        };
+
+        let is_valid_registry = |host: &str| {
+            !host.is_empty()
+                && host
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | ':'))
+        };
+        let is_valid_component = |component: &str| {
+            component.starts_with(|c: char| c.is_ascii_lowercase() || c.is_ascii_digit())
+                && component.chars().all(|c| {
+                    c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '.' | '_' | '-')
+                })
+        };
+        let is_valid_tag = |tag: &str| {
+            tag.len() <= 128
+                && tag.starts_with(|c: char| c.is_ascii_alphanumeric() || c == '_')
+                && tag
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+        };
+        let is_valid_digest = |digest: &str| {
+            digest.split_once(':').is_some_and(|(algorithm, hex)| {
+                !algorithm.is_empty()
+                    && !hex.is_empty()
+                    && algorithm
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '.' | '_' | '-'))
+                    && hex.chars().all(|c| c.is_ascii_hexdigit())
+            })
+        };
+
+        if !registry.is_none_or(is_valid_registry)
+            || !repository.split('/').all(is_valid_component)
+            || !tag.is_none_or(is_valid_tag)
+            || !digest.is_none_or(is_valid_digest)
+        {
+            return None;
+        }
+
+        Some(ImageRef {
+            registry: registry.map(String::from),
+            repository: repository.to_string(),
+            tag: tag.map(String::from),
+            digest: digest.map(String::from),
+        })
+    }
+
+    /// Registry host, falling back to [`ImageRef::DEFAULT_REGISTRY`]
+    pub fn registry_or_default(&self) -> &str {
+        self.registry.as_deref().unwrap_or(Self::DEFAULT_REGISTRY)
+    }
+
+    /// Tag, falling back to [`ImageRef::DEFAULT_TAG`]
+    ///
+    /// A digest-pinned reference without a tag also reports the default tag, although
+    /// the digest is what identifies the image.
+    pub fn tag_or_default(&self) -> &str {
+        self.tag.as_deref().unwrap_or(Self::DEFAULT_TAG)
+    }
+}
+
+/// Versionless identity of a feature key, or the key itself if it doesn't parse
+fn feature_identity(key: &str) -> String {
+    FeatureRef::parse(key)
+        .map(|feature| feature.id())
+        .unwrap_or_else(|| key.to_string())
+}
+
+/// Service port specification
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServicePort {
+    /// Service name
+    pub service: String,
+    /// Port number, or the first port of a range
+    pub port: u16,
+    /// Last port of a range (e.g., "db:5432-5434"), if any
+    pub end: Option<u16>,
+}
+
+impl ServicePort {
+    /// Parse a service:port string
+    ///
+    /// Uses rsplit_once to split on the rightmost colon, allowing service names
+    /// to contain colons. For example, "my:service:8080" parses as service="my:service", port=8080.
+    ///
+    /// The port may also be a `start-end` range, as in "db:5432-5434".
+    ///
+    /// Host tokens (`localhost` and IPv4 addresses) are rejected, since they name the
+    /// host rather than a service; see [`HostPort`].
+    pub fn parse(s: &str) -> Option<Self> {
+        let (service, port_str) = s.rsplit_once(':')?;
+
+        // Validate service name is not empty and not a host
+        if service.is_empty() || is_host_token(service) {
+            return None;
+        }
+
+        // Parse port or port range
+        let (port, end) = if port_str.contains('-') {
+            let range = PortRange::parse(port_str)?;
+            (range.start, Some(range.end))
+        } else {
+            (port_str.parse::<u16>().ok()?, None)
+        };
+
+        Some(ServicePort {
+            service: service.to_string(),
+            port,
+            end,
+        })
+    }
+
+    /// Ports of the service covered by this entry
+    pub fn ports(&self) -> core::ops::RangeInclusive<u16> {
+        self.port..=self.end.unwrap_or(self.port)
+    }
+}
+
+impl core::fmt::Display for ServicePort {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}:{}", self.service, self.port)?;
+        if let Some(end) = self.end {
+            write!(f, "-{}", end)?;
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for ServicePort {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for ServicePort {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        ServicePort::parse(&s).ok_or_else(|| {
+            serde::de::Error::custom("Expected format 'service:port' or 'service:start-end'")
+        })
+    }
+}
+
+/// Host-qualified port specification (e.g., "localhost:3000" or "127.0.0.1:3000")
+#[derive(Debug, Clone, PartialEq)]
+pub struct HostPort {
+    /// Host name or address
+    pub host: String,
+    /// Port number
+    pub port: u16,
+}
+
+impl HostPort {
+    /// Parse a host:port string
+    ///
+    /// Only known host tokens are accepted: `localhost` and IPv4 addresses. Anything
+    /// else before the colon is treated as a compose service name by [`ServicePort`].
+    pub fn parse(s: &str) -> Option<Self> {
+        let (host, port) = s.rsplit_once(':')?;
+        if !is_host_token(host) {
+            return None;
+        }
+        Some(HostPort {
+            host: host.to_string(),
+            port: port.parse::<u16>().ok()?,
+        })
+    }
+}
+
+/// Whether `host` names the host itself rather than a compose service
+fn is_host_token(host: &str) -> bool {
+    host.eq_ignore_ascii_case("localhost") || host.parse::<core::net::Ipv4Addr>().is_ok()
+}
+
+impl core::fmt::Display for HostPort {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}:{}", self.host, self.port)
+    }
+}
+
+impl Serialize for HostPort {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for HostPort {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        HostPort::parse(&s).ok_or_else(|| serde::de::Error::custom("Expected format 'host:port'"))
+    }
+}
+
+/// Port range specification (e.g., "3000-3005")
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PortRange {
+    /// First port in the range
+    pub start: u16,
+    /// Last port in the range (inclusive)
+    pub end: u16,
+}
+
+impl PortRange {
+    /// Parse a start-end string
+    ///
+    /// A bare number (e.g., a port written as the string `"3000"`) is read as a one-port
+    /// range. Returns `None` if either bound is not a port number or `start` exceeds `end`.
+    pub fn parse(s: &str) -> Option<Self> {
+        let (start, end) = s.split_once('-').unwrap_or((s, s));
+        let start = start.parse::<u16>().ok()?;
+        let end = end.parse::<u16>().ok()?;
+        (start <= end).then_some(PortRange { start, end })
+    }
+
+    /// Whether `port` falls within the range
+    pub fn contains(&self, port: u16) -> bool {
+        (self.start..=self.end).contains(&port)
+    }
+}
+
+impl core::fmt::Display for PortRange {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if self.start == self.end {
+            write!(f, "{}", self.start)
+        } else {
+            write!(f, "{}-{}", self.start, self.end)
+        }
+    }
+}
+
+impl Serialize for PortRange {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for PortRange {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        PortRange::parse(&s).ok_or_else(|| serde::de::Error::custom("Expected format 'start-end'"))
+    }
+}
+
+/// Port specification (can be a number, a range, host:port, service:port or an object)
+///
+/// A `host:port` string is only read as [`PortSpec::Host`] when the host is `localhost`
+/// or an IPv4 address; any other name is taken to be a compose service.
+///
+/// The object form (`{ "port": 3000, "label": "App" }`) is a convenience some
+/// generators emit; the crate's parse entry points (such as [`DevContainer::from_jsonc`])
+/// normalize it via [`DevContainer::normalize_port_labels`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PortSpec {
+    /// Numeric port
+    Number(u16),
+    /// Range of ports (e.g., "3000-3005")
+    Range(PortRange),
+    /// Port on a specific host interface (e.g., "localhost:3000")
+    Host(HostPort),
+    /// Service name with port specification (e.g., "db:5432")
+    Service(ServicePort),
+    /// Port given as an object with a label (e.g., `{ "port": 3000, "label": "App" }`)
+    Labeled(LabeledPort),
+}
+
+/// Object form of a forwarded port, see [`PortSpec::Labeled`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+#[cfg_attr(not(feature = "allow-unknown-fields"), serde(deny_unknown_fields))]
+pub struct LabeledPort {
+    /// Port number
+    pub port: u16,
+    /// Label to show for the port
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+
+    /// Additional unknown fields when allow-unknown-fields feature is enabled
+    ///
+    /// These are dropped when [`DevContainer::normalize_port_labels`] replaces the
+    /// entry with a plain port number.
+    #[cfg(feature = "allow-unknown-fields")]
+    #[serde(flatten)]
+    pub additional_fields: BTreeMap<String, serde_json::Value>,
+}
+
+impl PortSpec {
+    /// Single local port this entry resolves to, if any
+    ///
+    /// Numbers, one-port ranges (such as the string `"3000"`), host-qualified and labeled
+    /// ports resolve to their port; wider ranges and service ports don't.
+    pub fn as_number(&self) -> Option<u16> {
+        match self {
+            PortSpec::Number(port) => Some(*port),
+            PortSpec::Range(range) if range.start == range.end => Some(range.start),
+            PortSpec::Host(host) => Some(host.port),
+            PortSpec::Labeled(labeled) => Some(labeled.port),
+            _ => None,
+        }
+    }
+
+    /// Whether both entries resolve to the same local port, ignoring representation
+    ///
+    /// Entries that don't resolve to a single port fall back to structural equality.
+    pub fn same_port(&self, other: &PortSpec) -> bool {
+        match (self.as_number(), other.as_number()) {
+            (Some(port), Some(other_port)) => port == other_port,
+            (None, None) => self == other,
+            _ => false,
+        }
+    }
+
+    /// Local ports covered by this entry, or `None` for a port on another service
+    fn local_ports(&self) -> Option<core::ops::RangeInclusive<u16>> {
+        match self {
+            PortSpec::Number(port) => Some(*port..=*port),
+            PortSpec::Range(range) => Some(range.start..=range.end),
+            PortSpec::Host(host) => Some(host.port..=host.port),
+            PortSpec::Labeled(labeled) => Some(labeled.port..=labeled.port),
+            PortSpec::Service(_) => None,
+        }
+    }
+}
+
+/// Application port specification (a single port or a list of ports)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum AppPort {
+    /// Single port
+    Single(PortSpec),
+    /// List of ports
+    Multiple(Vec<PortSpec>),
+}
+
+impl AppPort {
+    /// All listed ports
+    pub fn ports(&self) -> &[PortSpec] {
+        match self {
+            AppPort::Single(port) => core::slice::from_ref(port),
+            AppPort::Multiple(ports) => ports,
+        }
+    }
+}
+
+/// Port attributes configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[non_exhaustive]
+#[cfg_attr(not(feature = "allow-unknown-fields"), serde(deny_unknown_fields))]
+pub struct PortAttributes {
+    /// Label for the port
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+
+    /// Protocol for the port
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub protocol: Option<PortProtocol>,
+
+    /// Action when auto-forwarding
+    #[serde(skip_serializing_if = "Option::is_none", rename = "onAutoForward")]
+    pub on_auto_forward: Option<OnAutoForward>,
+
+    /// Whether to require local port
+    #[serde(skip_serializing_if = "Option::is_none", rename = "requireLocalPort")]
+    pub require_local_port: Option<bool>,
+
+    /// Elevate if needed
+    #[serde(skip_serializing_if = "Option::is_none", rename = "elevateIfNeeded")]
+    pub elevate_if_needed: Option<bool>,
+
+    /// Additional unknown fields when allow-unknown-fields feature is enabled
+    #[cfg(feature = "allow-unknown-fields")]
+    #[serde(flatten)]
+    pub additional_fields: BTreeMap<String, serde_json::Value>,
+}
+
+impl PortAttributes {
+    /// Whether forwarding this port may need elevated privileges
+    ///
+    /// True only when both `requireLocalPort` (the same local port must be used, which
+    /// may be a privileged one) and `elevateIfNeeded` (the tool may elevate to bind it)
+    /// are set; otherwise the tool either picks another local port or doesn't elevate.
+    /// Unset values count as `false`.
+    pub fn requires_elevation(&self) -> bool {
+        self.require_local_port == Some(true) && self.elevate_if_needed == Some(true)
+    }
+
+    /// Whether auto-forwarding this port opens it in a browser or preview pane
+    pub fn opens_browser(&self) -> bool {
+        matches!(
+            self.on_auto_forward,
+            Some(
+                OnAutoForward::OpenBrowser
+                    | OnAutoForward::OpenBrowserOnce
+                    | OnAutoForward::OpenPreview
+            )
+        )
+    }
+}
+
+/// Protocol used when forwarding a port
+///
+/// Serialized as its lowercase name; unrecognized values are kept verbatim in
+/// [`PortProtocol::Other`] so they round-trip unchanged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PortProtocol {
+    /// Plain HTTP
+    Http,
+    /// HTTP over TLS
+    Https,
+    /// Any other protocol, as written
+    Other(String),
+}
+
+impl PortProtocol {
+    /// Name of the protocol as written in devcontainer.json
+    pub fn as_str(&self) -> &str {
+        match self {
+            PortProtocol::Http => "http",
+            PortProtocol::Https => "https",
+            PortProtocol::Other(other) => other,
+        }
+    }
+}
+
+impl From<&str> for PortProtocol {
+    fn from(s: &str) -> Self {
+        match s {
+            "http" => PortProtocol::Http,
+            "https" => PortProtocol::Https,
+            other => PortProtocol::Other(other.to_string()),
+        }
+    }
+}
+
+impl core::fmt::Display for PortProtocol {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for PortProtocol {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for PortProtocol {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        Ok(PortProtocol::from(s.as_str()))
+    }
+}
+
+/// Action taken when a port is auto-forwarded
+///
+/// Defaults to [`OnAutoForward::Notify`], the spec's behavior when none is given.
+/// Unrecognized actions are kept verbatim in [`OnAutoForward::Other`] so they
+/// round-trip unchanged.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum OnAutoForward {
+    /// Show a notification
+    #[default]
+    Notify,
+    /// Open the port in a browser
+    OpenBrowser,
+    /// Open the port in a browser, only the first time it is forwarded
+    OpenBrowserOnce,
+    /// Open the port in the editor's preview pane
+    OpenPreview,
+    /// Forward without notifying
+    Silent,
+    /// Don't auto-forward the port
+    Ignore,
+    /// Any other action, as written
+    Other(String),
+}
+
+impl OnAutoForward {
+    /// Name of the action as written in devcontainer.json
+    pub fn as_str(&self) -> &str {
+        match self {
+            OnAutoForward::Notify => "notify",
+            OnAutoForward::OpenBrowser => "openBrowser",
+            OnAutoForward::OpenBrowserOnce => "openBrowserOnce",
+            OnAutoForward::OpenPreview => "openPreview",
+            OnAutoForward::Silent => "silent",
+            OnAutoForward::Ignore => "ignore",
+            OnAutoForward::Other(other) => other,
+        }
+    }
+}
+
+impl From<&str> for OnAutoForward {
+    fn from(s: &str) -> Self {
+        match s {
+            "notify" => OnAutoForward::Notify,
+            "openBrowser" => OnAutoForward::OpenBrowser,
+            "openBrowserOnce" => OnAutoForward::OpenBrowserOnce,
+            "openPreview" => OnAutoForward::OpenPreview,
+            "silent" => OnAutoForward::Silent,
+            "ignore" => OnAutoForward::Ignore,
+            other => OnAutoForward::Other(other.to_string()),
+        }
+    }
+}
+
+impl core::fmt::Display for OnAutoForward {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for OnAutoForward {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for OnAutoForward {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        Ok(OnAutoForward::from(s.as_str()))
+    }
+}
+
+/// Interpretation of a `portsAttributes` key
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PortAttributeKey {
+    /// Single port (e.g., "3000")
+    Port(u16),
+    /// Port range (e.g., "40000-55000")
+    Range(PortRange),
+    /// Glob or regex matched against process command lines
+    Pattern,
+}
+
+impl PortAttributeKey {
+    fn parse(key: &str) -> Option<Self> {
+        if let Ok(port) = key.parse::<u16>() {
+            return Some(PortAttributeKey::Port(port));
+        }
+        if let Some(range) = PortRange::parse(key) {
+            return Some(PortAttributeKey::Range(range));
+        }
+        const PATTERN_CHARS: &[char] = &[
+            '*', '?', '[', ']', '{', '}', '(', ')', '^', '$', '|', '\\', '.', '+',
+        ];
+        key.contains(PATTERN_CHARS)
+            .then_some(PortAttributeKey::Pattern)
+    }
+}
+
+/// Basic command specification (shell or args)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum CommandSpec {
+    /// Shell command string
+    Shell(String),
+    /// Array of command arguments
+    Args(Vec<String>),
+}
+
+impl CommandSpec {
+    /// Whether the command is a string to be run by a shell
+    pub fn is_shell(&self) -> bool {
+        matches!(self, CommandSpec::Shell(_))
+    }
+
+    /// Render the command as one string for a POSIX shell
+    ///
+    /// A shell string is returned as is. Array arguments are joined with spaces, and
+    /// any argument that is empty or contains characters other than ASCII
+    /// alphanumerics and `-_./:=@%+,` is wrapped in single quotes.
+    pub fn to_shell_string(&self) -> String {
+        match self {
+            CommandSpec::Shell(command) => command.clone(),
+            CommandSpec::Args(args) => args
+                .iter()
+                .map(|arg| shell_quote(arg))
+                .collect::<Vec<_>>()
+                .join(" "),
+        }
+    }
+}
+
+/// Quote `arg` for a POSIX shell if it isn't a plain word
+fn shell_quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=@%+,".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        alloc::format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+/// `name`, or `name` with the first numeric suffix (from 2) that isn't a key of `taken`
+fn unused_name(name: &str, taken: &BTreeMap<String, CommandSpec>) -> String {
+    if !taken.contains_key(name) {
+        return name.to_string();
+    }
+    (2..)
+        .map(|n| alloc::format!("{}-{}", name, n))
+        .find(|candidate| !taken.contains_key(candidate))
+        .expect("a free suffix exists")
+}
+
+/// Lifecycle command specification (can be a command or object of commands)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum LifecycleCommand {
+    /// Single command
+    Command(CommandSpec),
+    /// Object with multiple named commands
+    Object(BTreeMap<String, CommandSpec>),
+}
+
+impl LifecycleCommand {
+    /// Whether this is an object of named commands to be run in parallel
+    pub fn is_parallel(&self) -> bool {
+        matches!(self, LifecycleCommand::Object(_))
+    }
+
+    /// Combine this command with `other` into a set of parallel commands
+    ///
+    /// Two objects are merged key by key, with `other` winning on name clashes. A
+    /// single command is first promoted to a one-entry object, named `"base"` when it
+    /// comes from `self` and `"override"` when it comes from `other`, so combining two
+    /// single commands runs both in parallel. If the other side already has a command
+    /// of that name, the promoted one is named `"base-2"`, `"base-3"` and so on
+    /// instead, so no command is dropped.
+    pub fn merge(&self, other: &LifecycleCommand) -> LifecycleCommand {
+        let mut commands = match (self, other) {
+            (LifecycleCommand::Command(spec), LifecycleCommand::Object(others)) => {
+                BTreeMap::from([(unused_name("base", others), spec.clone())])
+            }
+            _ => self.to_named("base"),
+        };
+        match other {
+            LifecycleCommand::Command(spec) => {
+                commands.insert(unused_name("override", &commands), spec.clone());
+            }
+            LifecycleCommand::Object(others) => commands.extend(others.clone()),
+        }
+        LifecycleCommand::Object(commands)
+    }
+
+    /// Name and rendered shell string of each command in an object, in key order
+    ///
+    /// Commands are rendered with [`CommandSpec::to_shell_string`]. A single command
+    /// yields no pairs.
+    pub fn named_shell_strings(&self) -> Vec<(String, String)> {
+        match self {
+            LifecycleCommand::Command(_) => Vec::new(),
+            LifecycleCommand::Object(commands) => commands
+                .iter()
+                .map(|(name, spec)| (name.clone(), spec.to_shell_string()))
+                .collect(),
+        }
+    }
+
+    /// Commands keyed by name, using `name` for a single command
+    fn to_named(&self, name: &str) -> BTreeMap<String, CommandSpec> {
+        match self {
+            LifecycleCommand::Command(spec) => BTreeMap::from([(name.to_string(), spec.clone())]),
+            LifecycleCommand::Object(commands) => commands.clone(),
+        }
+    }
+}
+
+/// Lifecycle phase in which a command runs
+///
+/// Serializes as the name of the corresponding devcontainer.json property.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum LifecyclePhase {
+    /// `onCreateCommand`
+    #[serde(rename = "onCreateCommand")]
+    OnCreate,
+    /// `updateContentCommand`
+    #[serde(rename = "updateContentCommand")]
+    UpdateContent,
+    /// `postCreateCommand`
+    #[serde(rename = "postCreateCommand")]
+    PostCreate,
+    /// `postStartCommand`
+    #[serde(rename = "postStartCommand")]
+    PostStart,
+    /// `postAttachCommand`
+    #[serde(rename = "postAttachCommand")]
+    PostAttach,
+}
+
+impl LifecyclePhase {
+    /// All phases in execution order
+    pub const ALL: &'static [LifecyclePhase] = &[
+        LifecyclePhase::OnCreate,
+        LifecyclePhase::UpdateContent,
+        LifecyclePhase::PostCreate,
+        LifecyclePhase::PostStart,
+        LifecyclePhase::PostAttach,
+    ];
+
+    /// Name of the devcontainer.json property for this phase
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LifecyclePhase::OnCreate => "onCreateCommand",
+            LifecyclePhase::UpdateContent => "updateContentCommand",
+            LifecyclePhase::PostCreate => "postCreateCommand",
+            LifecyclePhase::PostStart => "postStartCommand",
+            LifecyclePhase::PostAttach => "postAttachCommand",
+        }
+    }
+}
+
+/// Lifecycle command named by `waitFor`
+///
+/// Serialized as the devcontainer.json property name; unrecognized names are kept
+/// verbatim in [`WaitFor::Other`] so they round-trip unchanged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WaitFor {
+    /// `initializeCommand`, which runs on the host before the container is created
+    Initialize,
+    /// Command of a container lifecycle phase
+    Phase(LifecyclePhase),
+    /// Any other name, as written
+    Other(String),
+}
+
+impl WaitFor {
+    /// Name of the lifecycle command as written in devcontainer.json
+    pub fn as_str(&self) -> &str {
+        match self {
+            WaitFor::Initialize => "initializeCommand",
+            WaitFor::Phase(phase) => phase.as_str(),
+            WaitFor::Other(other) => other,
+        }
+    }
+}
+
+impl From<&str> for WaitFor {
+    fn from(s: &str) -> Self {
+        if s == "initializeCommand" {
+            return WaitFor::Initialize;
+        }
+        LifecyclePhase::ALL
+            .iter()
+            .find(|phase| phase.as_str() == s)
+            .map_or_else(
+                || WaitFor::Other(s.to_string()),
+                |&phase| WaitFor::Phase(phase),
+            )
+    }
+}
+
+impl From<LifecyclePhase> for WaitFor {
+    fn from(phase: LifecyclePhase) -> Self {
+        WaitFor::Phase(phase)
+    }
+}
+
+impl core::fmt::Display for WaitFor {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for WaitFor {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for WaitFor {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        Ok(WaitFor::from(s.as_str()))
+    }
+}
+
+/// Shutdown action
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+#[serde(rename_all = "camelCase")]
+pub enum ShutdownAction {
+    /// No action
+    None,
+    /// Stop the container
+    StopContainer,
+    /// Stop Docker Compose
+    #[cfg(feature = "docker-compose")]
+    StopCompose,
+}
+
+impl ShutdownAction {
+    /// Whether the container should be stopped when the tool closes
+    pub fn stops_container(&self) -> bool {
+        matches!(self, ShutdownAction::StopContainer)
+    }
+
+    /// Whether the Docker Compose project should be stopped when the tool closes
+    pub fn stops_compose(&self) -> bool {
+        #[cfg(feature = "docker-compose")]
+        if matches!(self, ShutdownAction::StopCompose) {
+            return true;
+        }
+        false
+    }
+}
+
+/// Description of a secret the user is prompted for (an entry of `secrets`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[non_exhaustive]
+#[cfg_attr(not(feature = "allow-unknown-fields"), serde(deny_unknown_fields))]
+pub struct SecretSpec {
+    /// What the secret is used for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// Where to find out how to obtain the secret
+    #[serde(skip_serializing_if = "Option::is_none", rename = "documentationUrl")]
+    pub documentation_url: Option<String>,
+
+    /// Additional unknown fields when allow-unknown-fields feature is enabled
+    #[cfg(feature = "allow-unknown-fields")]
+    #[serde(flatten)]
+    pub additional_fields: BTreeMap<String, serde_json::Value>,
+}
+
+/// Mount specification
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+#[non_exhaustive]
+#[cfg_attr(not(feature = "allow-unknown-fields"), serde(deny_unknown_fields))]
+pub struct MountSpec {
+    /// Source path
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+
+    /// Target path in container
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+
+    /// Mount type
+    #[serde(skip_serializing_if = "Option::is_none", rename = "type")]
+    pub mount_type: Option<MountType>,
+
+    /// Additional unknown fields when allow-unknown-fields feature is enabled
+    #[cfg(feature = "allow-unknown-fields")]
+    #[serde(flatten)]
+    pub additional_fields: BTreeMap<String, serde_json::Value>,
+}
+
+/// Kind of mount
+///
+/// Serialized as its lowercase name; unrecognized types are kept verbatim in
+/// [`MountType::Other`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MountType {
+    /// Bind mount of a host path
+    Bind,
+    /// Named or anonymous volume
+    Volume,
+    /// In-memory filesystem
+    Tmpfs,
+    /// Any other mount type, as written
+    Other(String),
+}
+
+impl MountType {
+    /// Name of the mount type as written in devcontainer.json
+    pub fn as_str(&self) -> &str {
+        match self {
+            MountType::Bind => "bind",
+            MountType::Volume => "volume",
+            MountType::Tmpfs => "tmpfs",
+            MountType::Other(other) => other,
+        }
+    }
+}
+
+impl From<&str> for MountType {
+    fn from(s: &str) -> Self {
+        match s {
+            "bind" => MountType::Bind,
+            "volume" => MountType::Volume,
+            "tmpfs" => MountType::Tmpfs,
+            other => MountType::Other(other.to_string()),
+        }
+    }
+}
+
+impl core::fmt::Display for MountType {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for MountType {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for MountType {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        Ok(MountType::from(s.as_str()))
+    }
+}
+
+/// Sort inclusive `(start, end)` port ranges and merge the overlapping ones
+///
+/// Adjacent ranges are merged too, but adjacent single ports are kept apart.
+fn merge_port_ranges(mut ranges: Vec<(u16, u16)>) -> Vec<(u16, u16)> {
+    ranges.sort_unstable();
+    let mut merged: Vec<(u16, u16)> = Vec::new();
+    for (start, end) in ranges {
+        if let Some(last) = merged.last_mut() {
+            let overlaps = start <= last.1;
+            let adjacent_ranges =
+                start == last.1.saturating_add(1) && start < end && last.0 < last.1;
+            if overlaps || adjacent_ranges {
+                last.1 = last.1.max(end);
+                continue;
+            }
+        }
+        merged.push((start, end));
+    }
+    merged
+}
+
+/// Split a short-form mount string into `key=value` pairs
+///
+/// A part without `=` (such as `readonly`) is yielded with an empty value.
+fn short_form_pairs(s: &str) -> impl Iterator<Item = (&str, &str)> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .map(|part| part.split_once('=').unwrap_or((part, "")))
+        .map(|(key, value)| (key.trim(), value.trim()))
+}
+
+impl MountSpec {
+    /// Parse a short-form mount string such as `source=x,target=/x,type=bind`
+    ///
+    /// Accepts the Docker aliases `src` for `source` and `destination`/`dst` for `target`.
+    /// Other options (e.g. `consistency=cached` or `readonly`) are kept in
+    /// `additional_fields` when `allow-unknown-fields` is enabled and dropped otherwise.
+    pub fn parse(s: &str) -> Option<Self> {
+        let mut mount = MountSpec::default();
+        let mut any = false;
+
+        for (key, value) in short_form_pairs(s) {
+            if key.is_empty() {
+                return None;
+            }
+            any = true;
+            match key {
+                "source" | "src" => mount.source = Some(value.to_string()),
+                "target" | "destination" | "dst" => mount.target = Some(value.to_string()),
+                "type" => mount.mount_type = Some(MountType::from(value)),
+                #[cfg(feature = "allow-unknown-fields")]
+                _ if value.is_empty() => {
+                    mount
+                        .additional_fields
+                        .insert(key.to_string(), serde_json::Value::Bool(true));
+                }
+                #[cfg(feature = "allow-unknown-fields")]
+                _ => {
+                    mount.additional_fields.insert(
+                        key.to_string(),
+                        serde_json::Value::String(value.to_string()),
+                    );
+                }
+                #[cfg(not(feature = "allow-unknown-fields"))]
+                _ => {}
+            }
+        }
+
+        any.then_some(mount)
+    }
+
+    /// Render the mount in short form (`type=...,source=...,target=...`)
+    ///
+    /// Keys are emitted in a fixed order and `None` fields are omitted. With
+    /// `allow-unknown-fields`, string and `true` options from `additional_fields`
+    /// follow in key order.
+    pub fn to_short_string(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(mount_type) = &self.mount_type {
+            parts.push(alloc::format!("type={}", mount_type));
+        }
+        if let Some(source) = &self.source {
+            parts.push(alloc::format!("source={}", source));
+        }
+        if let Some(target) = &self.target {
+            parts.push(alloc::format!("target={}", target));
+        }
+        #[cfg(feature = "allow-unknown-fields")]
+        for (key, value) in &self.additional_fields {
+            match value {
+                serde_json::Value::String(s) => parts.push(alloc::format!("{}={}", key, s)),
+                serde_json::Value::Bool(true) => parts.push(key.clone()),
+                _ => {}
+            }
+        }
+        parts.join(",")
+    }
+}
+
+/// Mount entry, given either in short form or as an object
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Mount {
+    /// Short-form mount string (e.g., "source=x,target=/x,type=bind")
+    String(String),
+    /// Structured mount specification
+    Object(MountSpec),
+}
+
+impl Mount {
+    /// Structured view of the mount, parsing the short form if needed
+    pub fn to_spec(&self) -> Option<MountSpec> {
+        match self {
+            Mount::String(s) => MountSpec::parse(s),
+            Mount::Object(spec) => Some(spec.clone()),
+        }
+    }
+}
+
+/// Docker Compose file specification
+#[cfg(feature = "docker-compose")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum DockerComposeFile {
+    /// Single file path
+    String(String),
+    /// Array of file paths
+    Array(Vec<String>),
+}
+
+/// Values used to resolve `${...}` variables in configuration strings
+#[derive(Debug, Clone, PartialEq, Default)]
+#[non_exhaustive]
+pub struct SubstitutionContext {
+    /// Workspace folder on the host, for `${localWorkspaceFolder}`
+    ///
+    /// Its last path component also provides `${localWorkspaceFolderBasename}`.
+    pub local_workspace_folder: Option<String>,
+    /// Host environment, for `${localEnv:NAME}` and `${localEnv:NAME:default}`
+    pub local_env: BTreeMap<String, String>,
+    /// Unique identifier of the dev container, for `${devcontainerId}`
+    pub devcontainer_id: Option<String>,
+    /// Workspace folder in the container, for `${containerWorkspaceFolder}`
+    ///
+    /// Its last path component also provides `${containerWorkspaceFolderBasename}`.
+    /// [`DevContainer::substituted`] prefers the configuration's `workspaceFolder`.
+    pub container_workspace_folder: Option<String>,
+}
+
+impl SubstitutionContext {
+    /// Replace every resolvable `${...}` variable in `input`
+    ///
+    /// Variables that are unknown or have no value in this context (including a
+    /// `localEnv` variable that is unset and has no default) are left as written.
+    pub fn substitute(&self, input: &str) -> String {
+        let mut output = String::with_capacity(input.len());
+        let mut rest = input;
+        while let Some(start) = rest.find("${") {
+            output.push_str(&rest[..start]);
+            let after = &rest[start + 2..];
+            let Some(end) = after.find('}') else {
+                rest = &rest[start..];
+                break;
+            };
+            let variable = &after[..end];
+            match self.resolve(variable) {
+                Some(value) => output.push_str(&value),
+                None => output.push_str(&rest[start..start + 2 + end + 1]),
+            }
+            rest = &after[end + 1..];
+        }
+        output.push_str(rest);
+        output
+    }
+
+    /// Value of a single variable (the text between `${` and `}`)
+    fn resolve(&self, variable: &str) -> Option<String> {
+        if let Some(local_env) = variable.strip_prefix("localEnv:") {
+            let (name, default) = match local_env.split_once(':') {
+                Some((name, default)) => (name, Some(default)),
+                None => (local_env, None),
+            };
+            return self
+                .local_env
+                .get(name)
+                .cloned()
+                .or_else(|| default.map(String::from));
+        }
+        match variable {
+            "localWorkspaceFolder" => self.local_workspace_folder.clone(),
+            "localWorkspaceFolderBasename" => self
+                .local_workspace_folder
+                .as_deref()
+                .map(|folder| path_basename(folder).to_string()),
+            "devcontainerId" => self.devcontainer_id.clone(),
+            "containerWorkspaceFolder" => self.container_workspace_folder.clone(),
+            "containerWorkspaceFolderBasename" => self
+                .container_workspace_folder
+                .as_deref()
+                .map(|folder| path_basename(folder).to_string()),
+            _ => None,
+        }
+    }
+}
+
+/// `${...}` variables referenced by `input`, without the braces
+///
+/// The default of a `localEnv` or `containerEnv` variable is dropped, so
+/// `${localEnv:FOO:bar}` yields `localEnv:FOO`.
+fn substitution_variables(input: &str) -> Vec<&str> {
+    let mut variables = Vec::new();
+    let mut rest = input;
+    while let Some(start) = rest.find("${") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find('}') else {
+            break;
+        };
+        let variable = &after[..end];
+        let variable = match variable.split_once(':') {
+            Some((kind @ ("localEnv" | "containerEnv"), name)) => {
+                let name_len = name.find(':').unwrap_or(name.len());
+                &variable[..kind.len() + 1 + name_len]
+            }
+            _ => variable,
+        };
+        if !variable.is_empty() {
+            variables.push(variable);
+        }
+        rest = &after[end + 1..];
+    }
+    variables
+}
+
+/// Last component of a `/` or `\` separated path, ignoring trailing separators
+fn path_basename(path: &str) -> &str {
+    let path = path.trim_end_matches(['/', '\\']);
+    path.rsplit(['/', '\\']).next().unwrap_or(path)
+}
+
+/// Severity of a [`Diagnostic`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[non_exhaustive]
+pub enum Severity {
+    /// Likely mistake that doesn't prevent the configuration from being used
+    Warning,
+    /// Invalid configuration
+    Error,
+}
+
+/// Issue found while checking a configuration
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Diagnostic {
+    /// How serious the issue is
+    pub severity: Severity,
+    /// devcontainer.json property the issue refers to (e.g., "updateRemoteUserUID")
+    pub field: String,
+    /// Human-readable description
+    pub message: String,
+}
+
+impl Diagnostic {
+    fn warning(field: &str, message: impl Into<String>) -> Self {
+        Diagnostic {
+            severity: Severity::Warning,
+            field: field.to_string(),
+            message: message.into(),
+        }
+    }
+
+    fn error(field: &str, message: impl Into<String>) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            field: field.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+impl core::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{}: {}: {}", severity, self.field, self.message)
+    }
+}
+
+/// Remove `//` line comments and `/* */` block comments from a JSONC document
+///
+/// Comment markers inside string literals are left untouched. Newlines inside comments
+/// are kept so line numbers in later parse errors still match the input. An unterminated
+/// block comment runs to the end of the input.
+pub fn strip_jsonc_comments(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut chars = input.chars().peekable();
+    let mut in_string = false;
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            output.push(c);
+            match c {
+                '\\' => output.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                output.push(c);
+            }
+            ('/', Some('/')) => while chars.next_if(|&next| next != '\n').is_some() {},
+            ('/', Some('*')) => {
+                chars.next();
+                let mut prev = '\0';
+                for next in chars.by_ref() {
+                    if prev == '*' && next == '/' {
+                        break;
+                    }
+                    if next == '\n' {
+                        output.push(next);
+                    }
+                    prev = next;
+                }
+            }
+            _ => output.push(c),
+        }
+    }
+
+    output
+}
+
+/// Error returned when parsing a devcontainer.json document fails
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ParseError {
+    /// The input is not valid JSON or doesn't match the devcontainer.json schema
+    Json(serde_json::Error),
+}
+
+impl core::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ParseError::Json(err) => write!(f, "invalid devcontainer.json: {}", err),
+        }
+    }
+}
+
+impl core::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            ParseError::Json(err) => Some(err),
+        }
+    }
+}
+
+impl From<serde_json::Error> for ParseError {
+    fn from(err: serde_json::Error) -> Self {
+        ParseError::Json(err)
+    }
+}
+
+/// Environment variable map that keeps variables in insertion (file) order
+///
+/// Used for `containerEnv` and `remoteEnv` when the `ordered-env` feature is enabled
+/// (see [`EnvMap`]). It (de)serializes as a JSON object like `BTreeMap` does.
+/// The order survives serializing to a string, but not converting to a
+/// `serde_json::Value`, whose objects are sorted by key.
+/// Inserting an existing key replaces its value in place; equality is order-sensitive.
+#[cfg(feature = "ordered-env")]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct OrderedEnvMap {
+    entries: Vec<(String, String)>,
+}
+
+#[cfg(feature = "ordered-env")]
+impl OrderedEnvMap {
+    /// Empty map
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of variables
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the map has no variables
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Value of `key`, if set
+    pub fn get<Q>(&self, key: &Q) -> Option<&String>
+    where
+        String: core::borrow::Borrow<Q>,
+        Q: PartialEq + ?Sized,
+    {
+        use core::borrow::Borrow;
+        self.entries
+            .iter()
+            .find(|(k, _)| k.borrow() == key)
+            .map(|(_, value)| value)
+    }
+
+    /// Whether `key` is set
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        String: core::borrow::Borrow<Q>,
+        Q: PartialEq + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    /// Set `key`, returning its previous value
+    ///
+    /// A new key goes last; an existing key keeps its position.
+    pub fn insert(&mut self, key: String, value: String) -> Option<String> {
+        match self.entries.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, existing)) => Some(core::mem::replace(existing, value)),
+            None => {
+                self.entries.push((key, value));
+                None
+            }
+        }
+    }
+
+    /// Remove `key`, returning its value
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        let index = self.entries.iter().position(|(k, _)| k == key)?;
+        Some(self.entries.remove(index).1)
+    }
+
+    /// Variables in insertion order
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &String)> {
+        self.entries.iter().map(|(key, value)| (key, value))
+    }
+
+    /// Variables in insertion order, with mutable values
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&String, &mut String)> {
+        self.entries.iter_mut().map(|(key, value)| (&*key, value))
+    }
+
+    /// Keys in insertion order
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.entries.iter().map(|(key, _)| key)
+    }
+
+    /// Values in insertion order
+    pub fn values(&self) -> impl Iterator<Item = &String> {
+        self.entries.iter().map(|(_, value)| value)
+    }
+
+    /// Mutable values in insertion order
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut String> {
+        self.entries.iter_mut().map(|(_, value)| value)
+    }
+}
+
+#[cfg(feature = "ordered-env")]
+impl core::ops::Index<&str> for OrderedEnvMap {
+    type Output = String;
+
+    fn index(&self, key: &str) -> &String {
+        self.get(key).expect("no entry found for key")
+    }
+}
+
+#[cfg(feature = "ordered-env")]
+impl Extend<(String, String)> for OrderedEnvMap {
+    fn extend<I: IntoIterator<Item = (String, String)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+#[cfg(feature = "ordered-env")]
+impl FromIterator<(String, String)> for OrderedEnvMap {
+    fn from_iter<I: IntoIterator<Item = (String, String)>>(iter: I) -> Self {
+        let mut map = OrderedEnvMap::new();
+        map.extend(iter);
+        map
+    }
+}
+
+#[cfg(feature = "ordered-env")]
+impl<'a> IntoIterator for &'a OrderedEnvMap {
+    type Item = (&'a String, &'a String);
+    type IntoIter = core::iter::Map<
+        core::slice::Iter<'a, (String, String)>,
+        fn(&'a (String, String)) -> (&'a String, &'a String),
+    >;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter().map(|(key, value)| (key, value))
+    }
+}
+
+#[cfg(feature = "ordered-env")]
+impl<'a> IntoIterator for &'a mut OrderedEnvMap {
+    type Item = (&'a String, &'a mut String);
+    type IntoIter = core::iter::Map<
+        core::slice::IterMut<'a, (String, String)>,
+        fn(&'a mut (String, String)) -> (&'a String, &'a mut String),
+    >;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter_mut().map(|(key, value)| (&*key, value))
+    }
+}
+
+#[cfg(feature = "ordered-env")]
+impl Serialize for OrderedEnvMap {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_map(self.iter())
+    }
+}
+
+#[cfg(feature = "ordered-env")]
+impl<'de> Deserialize<'de> for OrderedEnvMap {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct OrderedEnvMapVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for OrderedEnvMapVisitor {
+            type Value = OrderedEnvMap;
+
+            fn expecting(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                f.write_str("a map of environment variables")
+            }
+
+            fn visit_map<A>(self, mut access: A) -> Result<OrderedEnvMap, A::Error>
+            where
+                A: serde::de::MapAccess<'de>,
+            {
+                let mut map = OrderedEnvMap::new();
+                while let Some((key, value)) = access.next_entry::<String, String>()? {
+                    map.insert(key, value);
+                }
+                Ok(map)
+            }
+        }
+
+        deserializer.deserialize_map(OrderedEnvMapVisitor)
+    }
+}
+
+/// Error returned when `containerEnv` variables reference each other in a cycle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvCycleError {
+    /// Variables that could not be ordered, in map order (alphabetical, or file order with
+    /// `ordered-env`)
+    pub keys: Vec<String>,
+}
+
+impl core::fmt::Display for EnvCycleError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "containerEnv variables reference each other in a cycle: {}",
+            self.keys.join(", ")
+        )
+    }
+}
+
+impl core::error::Error for EnvCycleError {}
+
+/// Names of the environment variables referenced by an env value
+fn env_references(value: &str) -> Vec<&str> {
+    let is_name_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let mut names = Vec::new();
+    let mut rest = value;
+    while let Some(start) = rest.find('$') {
+        rest = &rest[start + 1..];
+        if let Some(braced) = rest.strip_prefix('{') {
+            let Some(end) = braced.find('}') else {
+                break;
+            };
+            let inner = &braced[..end];
+            let name = match inner.strip_prefix("containerEnv:") {
+                Some(name) => name.split(':').next().unwrap_or(name),
+                None => inner,
+            };
+            if !name.is_empty() && name.chars().all(is_name_char) {
+                names.push(name);
+            }
+            rest = &braced[end + 1..];
+        } else {
+            let end = rest.find(|c| !is_name_char(c)).unwrap_or(rest.len());
+            if end > 0 {
+                names.push(&rest[..end]);
+            }
+            rest = &rest[end..];
+        }
+    }
+    names
+}
+
+/// Body of [`walk_json_strings`] and [`walk_json_strings_mut`], see [`walk_strings_body`]
+macro_rules! walk_json_strings_body {
+    ($value:ident, $f:ident, $iter:ident, $values:ident, $recurse:ident) => {
+        match $value {
+            serde_json::Value::String(s) => $f(s),
+            serde_json::Value::Array(values) => {
+                values.$iter().for_each(|value| $recurse(value, $f))
+            }
+            serde_json::Value::Object(map) => map.$values().for_each(|value| $recurse(value, $f)),
+            _ => {}
+        }
+    };
+}
+
+/// Apply `f` to every string leaf of a JSON value (object keys are left alone)
+fn walk_json_strings_mut(value: &mut serde_json::Value, f: &mut dyn FnMut(&mut String)) {
+    walk_json_strings_body!(value, f, iter_mut, values_mut, walk_json_strings_mut)
+}
+
+/// Read-only counterpart of [`walk_json_strings_mut`]
+fn walk_json_strings(value: &serde_json::Value, f: &mut dyn FnMut(&String)) {
+    walk_json_strings_body!(value, f, iter, values, walk_json_strings)
+}
+
+/// Top-level properties of a serialized [`DevContainer`], written with the env maps
+/// taken from the typed configuration
+///
+/// A `serde_json::Map` is sorted by key, which would lose the file order of an
+/// [`EnvMap`] under `ordered-env`.
+struct EnvOrderedProperties<'a> {
+    properties: &'a serde_json::Map<String, serde_json::Value>,
+    devcontainer: &'a DevContainer,
+}
+
+impl Serialize for EnvOrderedProperties<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeMap;
+
+        let mut map = serializer.serialize_map(Some(self.properties.len()))?;
+        for (key, value) in self.properties {
+            match key.as_str() {
+                "containerEnv" => map.serialize_entry(key, &self.devcontainer.container_env)?,
+                "remoteEnv" => map.serialize_entry(key, &self.devcontainer.remote_env)?,
+                _ => map.serialize_entry(key, value)?,
+            }
+        }
+        map.end()
+    }
+}
+
+/// Whether `value` is an empty JSON array or object
+fn is_empty_collection(value: &serde_json::Value) -> bool {
+    match value {
+        serde_json::Value::Array(values) => values.is_empty(),
+        serde_json::Value::Object(map) => map.is_empty(),
+        _ => false,
+    }
+}
+
+/// Replace `base` with `other` when `other` is set
+fn merge_replace<T: Clone>(base: &mut Option<T>, other: &Option<T>) {
+    if other.is_some() {
+        base.clone_from(other);
+    }
+}
+
+/// Merge two optional maps key by key, with `other` winning on conflicts
+fn merge_keys<V: Clone>(
+    base: &mut Option<BTreeMap<String, V>>,
+    other: &Option<BTreeMap<String, V>>,
+) {
+    if let Some(other) = other {
+        base.get_or_insert_with(BTreeMap::new).extend(
+            other
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone())),
+        );
+    }
+}
+
+/// Merge two optional env maps key by key, with `other` winning
+fn merge_env(base: &mut Option<EnvMap>, other: &Option<EnvMap>) {
+    if let Some(other) = other {
+        base.get_or_insert_with(EnvMap::default).extend(
+            other
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone())),
+        );
+    }
+}
+
+/// Deep-merge two optional JSON object maps (see [`merge_json`])
+fn merge_deep(
+    base: &mut Option<BTreeMap<String, serde_json::Value>>,
+    other: &Option<BTreeMap<String, serde_json::Value>>,
+) {
+    if let Some(other) = other {
+        let base = base.get_or_insert_with(BTreeMap::new);
+        for (key, value) in other {
+            match base.get_mut(key) {
+                Some(existing) => merge_json(existing, value),
+                None => {
+                    base.insert(key.clone(), value.clone());
+                }
+            }
+        }
+    }
+}
+
+/// Recursively merge `other` into `base`: objects combine, any other value replaces
+fn merge_json(base: &mut serde_json::Value, other: &serde_json::Value) {
+    match (base, other) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(other)) => {
+            for (key, value) in other {
+                match base.get_mut(key) {
+                    Some(existing) => merge_json(existing, value),
+                    None => {
+                        base.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (base, other) => *base = other.clone(),
+    }
+}
+
+/// Deserialize an optional field holding either a single value or an array of values
+fn one_or_many<'de, D, T>(deserializer: D) -> Result<Option<Vec<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany<T> {
+        Many(Vec<T>),
+        One(T),
+    }
+
+    Ok(
+        Option::<OneOrMany<T>>::deserialize(deserializer)?.map(|value| match value {
+            OneOrMany::Many(values) => values,
+            OneOrMany::One(value) => alloc::vec![value],
+        }),
+    )
+}
+
+/// Split a command line into arguments
+///
+/// Arguments are separated by whitespace. Single or double quotes group characters,
+/// including whitespace, into one argument and are removed; there are no escape
+/// sequences, variable expansion or other shell syntax.
+pub fn split_args(s: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quote = None;
+
+    for c in s.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => current.push(c),
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                in_arg = true;
+            }
+            None if c.is_whitespace() => {
+                if in_arg {
+                    args.push(core::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            None => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if in_arg {
+        args.push(current);
+    }
+    args
+}
+
+/// Deserialize optional arguments given as an array or as one string (see [`split_args`])
+fn args_or_string<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum ArgsOrString {
+        Args(Vec<String>),
+        String(String),
+    }
+
+    Ok(
+        Option::<ArgsOrString>::deserialize(deserializer)?.map(|value| match value {
+            ArgsOrString::Args(args) => args,
+            ArgsOrString::String(s) => split_args(&s),
+        }),
+    )
+}
+
+/// Deserialize an optional boolean written either as a JSON boolean or as `"true"`/`"false"`
+fn lenient_bool<'de, D>(deserializer: D) -> Result<Option<bool>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum BoolOrString {
+        Bool(bool),
+        String(String),
+    }
+
+    match Option::<BoolOrString>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(BoolOrString::Bool(value)) => Ok(Some(value)),
+        Some(BoolOrString::String(s)) => match s.as_str() {
+            "true" => Ok(Some(true)),
+            "false" => Ok(Some(false)),
+            _ => Err(serde::de::Error::custom(
+                "Expected a boolean or the string 'true' or 'false'",
+            )),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    #[test]
+    fn test_basic_devcontainer() {
+        let json = r#"{
+            "name": "Test Container",
+            "image": "mcr.microsoft.com/devcontainers/rust:latest"
+        }"#;
+
+        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
+        assert_eq!(devcontainer.name, Some("Test Container".to_string()));
+        assert_eq!(
+            devcontainer.image,
             Some("mcr.microsoft.com/devcontainers/rust:latest".to_string())
         );
     }
 
     #[test]
-    fn test_with_build_config() {
-        let json = r#"{
-            "name": "Custom Build",
-            "build": {
-                "dockerfile": "Dockerfile",
-                "context": "..",
-                "args": {
-                    "VARIANT": "bullseye"
+    fn test_with_build_config() {
+        let json = r#"{
+            "name": "Custom Build",
+            "build": {
+                "dockerfile": "Dockerfile",
+                "context": "..",
+                "args": {
+                    "VARIANT": "bullseye"
+                }
+            }
+        }"#;
+
+        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
+        assert!(devcontainer.build.is_some());
+        let build = devcontainer.build.unwrap();
+        assert_eq!(build.dockerfile, Some("Dockerfile".to_string()));
+        assert_eq!(build.context, Some("..".to_string()));
+    }
+
+    #[test]
+    #[cfg(feature = "vscode")]
+    fn test_with_features_and_extensions() {
+        let json = r#"{
+            "name": "Feature Test",
+            "image": "ubuntu:latest",
+            "features": {
+                "ghcr.io/devcontainers/features/docker-in-docker:2": {}
+            },
+            "extensions": [
+                "dbaeumer.vscode-eslint",
+                "esbenp.prettier-vscode"
+            ]
+        }"#;
+
+        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
+        assert!(devcontainer.features.is_some());
+        assert!(devcontainer.extensions.is_some());
+        assert_eq!(devcontainer.extensions.unwrap().len(), 2);
+    }
+
+    #[test]
+    #[cfg(not(feature = "vscode"))]
+    fn test_with_features_no_vscode() {
+        let json = r#"{
+            "name": "Feature Test",
+            "image": "ubuntu:latest",
+            "features": {
+                "ghcr.io/devcontainers/features/docker-in-docker:2": {}
+            }
+        }"#;
+
+        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
+        assert!(devcontainer.features.is_some());
+    }
+
+    #[test]
+    fn test_port_forwarding() {
+        let json = r#"{
+            "name": "Port Test",
+            "image": "node:18",
+            "forwardPorts": [3000, "db:5432"],
+            "portsAttributes": {
+                "3000": {
+                    "label": "Application",
+                    "onAutoForward": "notify"
+                }
+            }
+        }"#;
+
+        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
+        assert!(devcontainer.forward_ports.is_some());
+        assert!(devcontainer.ports_attributes.is_some());
+    }
+
+    #[test]
+    fn test_lifecycle_commands() {
+        let json = r#"{
+            "name": "Lifecycle Test",
+            "image": "node:18",
+            "postCreateCommand": "npm install",
+            "postStartCommand": ["npm", "run", "dev"]
+        }"#;
+
+        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
+        assert!(devcontainer.post_create_command.is_some());
+        assert!(devcontainer.post_start_command.is_some());
+    }
+
+    #[test]
+    fn test_environment_variables() {
+        let json = r#"{
+            "name": "Env Test",
+            "image": "python:3.9",
+            "containerEnv": {
+                "MY_VAR": "value",
+                "ANOTHER_VAR": "another_value"
+            },
+            "remoteUser": "vscode"
+        }"#;
+
+        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
+        assert!(devcontainer.container_env.is_some());
+        assert_eq!(devcontainer.remote_user, Some("vscode".to_string()));
+    }
+
+    #[test]
+    #[allow(clippy::field_reassign_with_default)]
+    fn test_serialization_roundtrip() {
+        let mut devcontainer = DevContainer::default();
+        devcontainer.name = Some("Test".to_string());
+        devcontainer.image = Some("ubuntu:latest".to_string());
+        #[cfg(feature = "vscode")]
+        {
+            devcontainer.extensions = Some(vec!["test.extension".to_string()]);
+        }
+
+        let json = serde_json::to_string(&devcontainer).unwrap();
+        let parsed: DevContainer = serde_json::from_str(&json).unwrap();
+        assert_eq!(devcontainer, parsed);
+    }
+
+    #[cfg(not(feature = "allow-unknown-fields"))]
+    #[test]
+    fn test_unknown_fields_rejected_by_default() {
+        let json = r#"{
+            "name": "Test",
+            "image": "ubuntu:latest",
+            "unknownField": "should fail"
+        }"#;
+
+        let result: Result<DevContainer, _> = serde_json::from_str(json);
+        assert!(result.is_err());
+    }
+
+    #[cfg(feature = "allow-unknown-fields")]
+    #[test]
+    fn test_unknown_fields_captured_with_feature() {
+        let json = r#"{
+            "name": "Test",
+            "image": "ubuntu:latest",
+            "unknownField": "should be captured",
+            "anotherUnknown": 42
+        }"#;
+
+        let result: DevContainer = serde_json::from_str(json).unwrap();
+        assert_eq!(result.name, Some("Test".to_string()));
+        assert_eq!(result.additional_fields.len(), 2);
+        assert!(result.additional_fields.contains_key("unknownField"));
+        assert!(result.additional_fields.contains_key("anotherUnknown"));
+    }
+
+    #[test]
+    fn test_default_implementations() {
+        // Test Default for DevContainer
+        let devcontainer = DevContainer::default();
+        assert_eq!(devcontainer.name, None);
+        assert_eq!(devcontainer.image, None);
+
+        // Test Default for BuildConfig
+        let build_config = BuildConfig::default();
+        assert_eq!(build_config.dockerfile, None);
+        assert_eq!(build_config.context, None);
+
+        // Test Default for PortAttributes
+        let port_attrs = PortAttributes::default();
+        assert_eq!(port_attrs.label, None);
+
+        // Test Default for MountSpec
+        let mount = MountSpec::default();
+        assert_eq!(mount.source, None);
+    }
+
+    #[test]
+    #[allow(clippy::field_reassign_with_default)]
+    fn test_default_with_modifications() {
+        // Demonstrate using Default as a builder pattern
+        let mut devcontainer = DevContainer::default();
+        devcontainer.name = Some("Test Container".to_string());
+        devcontainer.image = Some("ubuntu:latest".to_string());
+
+        let json = serde_json::to_string(&devcontainer).unwrap();
+        let parsed: DevContainer = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.name, Some("Test Container".to_string()));
+        assert_eq!(parsed.image, Some("ubuntu:latest".to_string()));
+    }
+
+    #[test]
+    fn test_mount_short_string_roundtrip() {
+        let mount =
+            MountSpec::parse("src=/var/run/docker.sock, dst=/var/run/docker.sock,type=bind")
+                .unwrap();
+        assert_eq!(mount.source, Some("/var/run/docker.sock".to_string()));
+        assert_eq!(mount.target, Some("/var/run/docker.sock".to_string()));
+        assert_eq!(mount.mount_type, Some(MountType::Bind));
+
+        let short = mount.to_short_string();
+        assert_eq!(
+            short,
+            "type=bind,source=/var/run/docker.sock,target=/var/run/docker.sock"
+        );
+        assert_eq!(MountSpec::parse(&short), Some(mount));
+
+        let volume = MountSpec {
+            target: Some("/data".to_string()),
+            mount_type: Some(MountType::Volume),
+            ..Default::default()
+        };
+        assert_eq!(volume.to_short_string(), "type=volume,target=/data");
+        assert_eq!(MountSpec::parse(""), None);
+    }
+
+    #[test]
+    fn test_feature_ref_canonical() {
+        let feature = FeatureRef::parse("ghcr.io/devcontainers/features/node").unwrap();
+        assert_eq!(
+            feature.canonical(),
+            "ghcr.io/devcontainers/features/node:latest"
+        );
+
+        let feature = FeatureRef::parse("ghcr.io/devcontainers/features/node:2.1.0").unwrap();
+        assert_eq!(
+            feature,
+            FeatureRef::Oci {
+                registry: "ghcr.io".to_string(),
+                namespace: "devcontainers/features".to_string(),
+                name: "node".to_string(),
+                version: Some("2.1.0".to_string()),
+                digest: None,
+            }
+        );
+        assert_eq!(
+            feature.canonical(),
+            "ghcr.io/devcontainers/features/node:2.1.0"
+        );
+
+        let feature = FeatureRef::parse("localhost:5000/team/tool@sha256:abc").unwrap();
+        assert_eq!(feature.canonical(), "localhost:5000/team/tool@sha256:abc");
+
+        assert_eq!(FeatureRef::parse("node"), None);
+        assert_eq!(
+            FeatureRef::parse("ghcr.io/devcontainers/features/node:"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_duplicate_features() {
+        let json = r#"{
+            "image": "ubuntu:latest",
+            "features": {
+                "ghcr.io/devcontainers/features/node": {},
+                "ghcr.io/devcontainers/features/node:latest": {},
+                "ghcr.io/devcontainers/features/go:1": {},
+                "ghcr.io/devcontainers/features/go:1.2": {}
+            }
+        }"#;
+
+        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            devcontainer.duplicate_features(),
+            vec!["ghcr.io/devcontainers/features/node:latest".to_string()]
+        );
+        assert!(DevContainer::default().duplicate_features().is_empty());
+    }
+
+    #[test]
+    fn test_execution_plan_parallel_phase() {
+        let json = r#"{
+            "image": "node:18",
+            "onCreateCommand": "npm ci",
+            "postAttachCommand": {
+                "server": "npm start",
+                "watch": ["npm", "run", "watch"]
+            }
+        }"#;
+
+        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
+        let plan = devcontainer.execution_plan();
+        assert_eq!(plan.len(), 3);
+        assert_eq!(
+            plan[0],
+            (
+                LifecyclePhase::OnCreate,
+                None,
+                &CommandSpec::Shell("npm ci".to_string())
+            )
+        );
+        assert_eq!(plan[1].0, LifecyclePhase::PostAttach);
+        assert_eq!(plan[1].1, Some("server"));
+        assert_eq!(plan[2].0, LifecyclePhase::PostAttach);
+        assert_eq!(plan[2].1, Some("watch"));
+    }
+
+    #[test]
+    fn test_execution_plan_single_command() {
+        let json = r#"{
+            "image": "node:18",
+            "postAttachCommand": "npm start"
+        }"#;
+
+        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            devcontainer.execution_plan(),
+            vec![(
+                LifecyclePhase::PostAttach,
+                None,
+                &CommandSpec::Shell("npm start".to_string())
+            )]
+        );
+        assert!(DevContainer::default().execution_plan().is_empty());
+    }
+
+    #[test]
+    fn test_mounts_single_string() {
+        let json = r#"{
+            "image": "ubuntu:latest",
+            "mounts": "source=x,target=/x,type=bind"
+        }"#;
+
+        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
+        let mounts = devcontainer.mounts.unwrap();
+        assert_eq!(
+            mounts,
+            vec![Mount::String("source=x,target=/x,type=bind".to_string())]
+        );
+        assert_eq!(mounts[0].to_spec().unwrap().target, Some("/x".to_string()));
+    }
+
+    #[test]
+    fn test_mounts_single_object() {
+        let json = r#"{
+            "image": "ubuntu:latest",
+            "mounts": { "source": "cache", "target": "/cache", "type": "volume" }
+        }"#;
+
+        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
+        let mounts = devcontainer.mounts.unwrap();
+        assert_eq!(mounts.len(), 1);
+        assert!(
+            matches!(&mounts[0], Mount::Object(spec) if spec.source.as_deref() == Some("cache"))
+        );
+    }
+
+    #[test]
+    fn test_mounts_array() {
+        let json = r#"{
+            "image": "ubuntu:latest",
+            "mounts": [
+                "source=x,target=/x,type=bind",
+                { "source": "cache", "target": "/cache", "type": "volume" }
+            ]
+        }"#;
+
+        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
+        let mounts = devcontainer.mounts.unwrap();
+        assert_eq!(mounts.len(), 2);
+        assert!(matches!(mounts[0], Mount::String(_)));
+        assert!(matches!(mounts[1], Mount::Object(_)));
+    }
+
+    #[test]
+    fn test_topologically_ordered_env() {
+        let json = r#"{
+            "image": "ubuntu:latest",
+            "containerEnv": {
+                "APP_URL": "http://${containerEnv:HOST}:$PORT",
+                "HOST": "localhost",
+                "PATH": "${containerEnv:PATH}:/opt/bin",
+                "PORT": "${BASE_PORT}",
+                "BASE_PORT": "3000"
+            }
+        }"#;
+
+        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
+        let ordered = devcontainer.topologically_ordered_env().unwrap();
+        let keys: Vec<&str> = ordered.iter().map(|(key, _)| key.as_str()).collect();
+        #[cfg(not(feature = "ordered-env"))]
+        {
+            assert_eq!(keys, vec!["BASE_PORT", "HOST", "PATH", "PORT", "APP_URL"]);
+            assert_eq!(ordered[0].1, "3000");
+        }
+        // Unconstrained keys keep file order
+        #[cfg(feature = "ordered-env")]
+        assert_eq!(keys, vec!["HOST", "PATH", "BASE_PORT", "PORT", "APP_URL"]);
+    }
+
+    #[test]
+    fn test_topologically_ordered_env_cycle() {
+        let json = r#"{
+            "image": "ubuntu:latest",
+            "containerEnv": {
+                "A": "${containerEnv:B}",
+                "B": "$A",
+                "C": "plain"
+            }
+        }"#;
+
+        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
+        let err = devcontainer.topologically_ordered_env().unwrap_err();
+        assert_eq!(err.keys, vec!["A".to_string(), "B".to_string()]);
+        assert_eq!(
+            err.to_string(),
+            "containerEnv variables reference each other in a cycle: A, B"
+        );
+
+        let reversed: DevContainer =
+            serde_json::from_str(r#"{ "containerEnv": { "Z": "${containerEnv:Y}", "Y": "$Z" } }"#)
+                .unwrap();
+        let err = reversed.topologically_ordered_env().unwrap_err();
+        #[cfg(not(feature = "ordered-env"))]
+        assert_eq!(err.keys, vec!["Y".to_string(), "Z".to_string()]);
+        #[cfg(feature = "ordered-env")]
+        assert_eq!(err.keys, vec!["Z".to_string(), "Y".to_string()]);
+    }
+
+    #[test]
+    fn test_image_ref_docker_hub() {
+        let image = ImageRef::parse("ubuntu:22.04").unwrap();
+        assert_eq!(image.registry, None);
+        assert_eq!(image.registry_or_default(), "docker.io");
+        assert_eq!(image.repository, "ubuntu");
+        assert_eq!(image.tag, Some("22.04".to_string()));
+        assert_eq!(image.digest, None);
+
+        let image = ImageRef::parse("ubuntu").unwrap();
+        assert_eq!(image.tag_or_default(), "latest");
+    }
+
+    #[test]
+    fn test_image_ref_with_registry() {
+        let devcontainer = DevContainer {
+            image: Some("mcr.microsoft.com/devcontainers/rust:latest".to_string()),
+            ..Default::default()
+        };
+
+        let image = devcontainer.image_ref().unwrap();
+        assert_eq!(image.registry, Some("mcr.microsoft.com".to_string()));
+        assert_eq!(image.repository, "devcontainers/rust");
+        assert_eq!(image.tag, Some("latest".to_string()));
+
+        let image = ImageRef::parse("localhost:5000/team/app").unwrap();
+        assert_eq!(image.registry, Some("localhost:5000".to_string()));
+        assert_eq!(image.repository, "team/app");
+        assert_eq!(image.tag, None);
+    }
+
+    #[test]
+    fn test_image_ref_with_digest() {
+        let image = ImageRef::parse(
+            "ghcr.io/org/app@sha256:0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef",
+        )
+        .unwrap();
+        assert_eq!(image.registry, Some("ghcr.io".to_string()));
+        assert_eq!(image.repository, "org/app");
+        assert_eq!(image.tag, None);
+        assert_eq!(
+            image.digest,
+            Some(
+                "sha256:0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef"
+                    .to_string()
+            )
+        );
+
+        assert_eq!(ImageRef::parse("ubuntu::"), None);
+        assert_eq!(ImageRef::parse(""), None);
+        assert_eq!(ImageRef::parse("ubuntu@sha256:"), None);
+    }
+
+    #[test]
+    fn test_merge_deep_merges_vscode_settings() {
+        let base: DevContainer = serde_json::from_str(
+            r#"{
+                "image": "ubuntu:22.04",
+                "containerEnv": { "A": "1", "B": "1" },
+                "customizations": {
+                    "vscode": {
+                        "settings": {
+                            "editor": { "tabSize": 4, "formatOnSave": true },
+                            "files.eol": "\n"
+                        }
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+        let other: DevContainer = serde_json::from_str(
+            r#"{
+                "image": "ubuntu:24.04",
+                "containerEnv": { "B": "2" },
+                "customizations": {
+                    "vscode": {
+                        "settings": {
+                            "editor": { "tabSize": 2, "rulers": [80] }
+                        }
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let mut merged = base.clone();
+        merged.merge(&other);
+
+        assert_eq!(merged.image, Some("ubuntu:24.04".to_string()));
+        let env = merged.container_env.as_ref().unwrap();
+        assert_eq!(env["A"], "1");
+        assert_eq!(env["B"], "2");
+
+        let settings = &merged.customizations.as_ref().unwrap()["vscode"]["settings"];
+        assert_eq!(
+            settings,
+            &serde_json::json!({
+                "editor": { "tabSize": 2, "formatOnSave": true, "rulers": [80] },
+                "files.eol": "\n"
+            })
+        );
+    }
+
+    #[test]
+    fn test_lint_update_remote_user_uid_without_remote_user() {
+        let json = r#"{
+            "image": "ubuntu:latest",
+            "updateRemoteUserUID": true
+        }"#;
+
+        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
+        let diagnostics = devcontainer.lint();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+        assert_eq!(diagnostics[0].field, "updateRemoteUserUID");
+    }
+
+    #[test]
+    fn test_lint_update_remote_user_uid_with_remote_user() {
+        let json = r#"{
+            "image": "ubuntu:latest",
+            "remoteUser": "vscode",
+            "updateRemoteUserUID": true
+        }"#;
+
+        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
+        assert!(devcontainer.lint().is_empty());
+    }
+
+    #[test]
+    fn test_all_ports_deduplicated_and_sorted() {
+        let json = r#"{
+            "image": "node:18",
+            "forwardPorts": [8080, "3000-3002", "db:5432"],
+            "appPort": [3001, 9000, 8080]
+        }"#;
+
+        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            devcontainer.forward_ports.as_ref().unwrap()[1],
+            PortSpec::Range(PortRange {
+                start: 3000,
+                end: 3002
+            })
+        );
+        let ports: Vec<u16> = devcontainer.all_ports().collect();
+        assert_eq!(ports, vec![3000, 3001, 3002, 8080, 9000]);
+
+        let single: DevContainer =
+            serde_json::from_str(r#"{ "image": "node:18", "appPort": 3000 }"#).unwrap();
+        assert_eq!(single.all_ports().collect::<Vec<_>>(), vec![3000]);
+    }
+
+    #[test]
+    fn test_resolved_build_args() {
+        let json = r#"{
+            "build": {
+                "dockerfile": "Dockerfile",
+                "args": {
+                    "TOKEN": "${localEnv:NPM_TOKEN}",
+                    "VARIANT": "${localEnv:VARIANT:bullseye}",
+                    "OTHER": "${localEnv:MISSING}-${unknown}"
+                }
+            }
+        }"#;
+
+        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
+        let mut ctx = SubstitutionContext::default();
+        ctx.local_env
+            .insert("NPM_TOKEN".to_string(), "abc123".to_string());
+
+        let args = devcontainer.resolved_build_args(&ctx).unwrap();
+        assert_eq!(args["TOKEN"], "abc123");
+        assert_eq!(args["VARIANT"], "bullseye");
+        assert_eq!(args["OTHER"], "${localEnv:MISSING}-${unknown}");
+        assert_eq!(DevContainer::default().resolved_build_args(&ctx), None);
+    }
+
+    #[test]
+    fn test_to_minimal_json_omits_empty_collections() {
+        let devcontainer = DevContainer {
+            image: Some("ubuntu:latest".to_string()),
+            forward_ports: Some(vec![]),
+            container_env: Some(EnvMap::default()),
+            build: Some(BuildConfig {
+                args: Some(BTreeMap::new()),
+                ..Default::default()
+            }),
+            features: Some(BTreeMap::from([(
+                "ghcr.io/devcontainers/features/node:1".to_string(),
+                serde_json::json!({}),
+            )])),
+            ..Default::default()
+        };
+
+        let plain = serde_json::to_string(&devcontainer).unwrap();
+        assert!(plain.contains(r#""forwardPorts":[]"#));
+
+        let minimal = devcontainer.to_minimal_json();
+        assert_eq!(
+            minimal,
+            r#"{"features":{"ghcr.io/devcontainers/features/node:1":{}},"image":"ubuntu:latest"}"#
+        );
+    }
+
+    #[test]
+    fn test_substituted_devcontainer_id() {
+        let json = r#"{
+            "image": "ubuntu:latest",
+            "containerEnv": { "CACHE_KEY": "cache-${devcontainerId}" },
+            "mounts": [
+                "source=history-${devcontainerId},target=/commandhistory,type=volume",
+                { "source": "${devcontainerId}-data", "target": "/data", "type": "volume" }
+            ]
+        }"#;
+
+        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
+        let ctx = SubstitutionContext {
+            devcontainer_id: Some("abc123".to_string()),
+            ..Default::default()
+        };
+        let resolved = devcontainer.substituted(&ctx);
+
+        assert_eq!(resolved.container_env.unwrap()["CACHE_KEY"], "cache-abc123");
+        let mounts = resolved.mounts.unwrap();
+        assert_eq!(
+            mounts[0],
+            Mount::String("source=history-abc123,target=/commandhistory,type=volume".to_string())
+        );
+        assert_eq!(
+            mounts[1].to_spec().unwrap().source,
+            Some("abc123-data".to_string())
+        );
+        // The original is untouched
+        assert_eq!(
+            devcontainer.container_env.unwrap()["CACHE_KEY"],
+            "cache-${devcontainerId}"
+        );
+    }
+
+    #[test]
+    fn test_customizations_typed() {
+        let json = r#"{
+            "image": "ubuntu:latest",
+            "customizations": {
+                "vscode": {
+                    "extensions": ["rust-lang.rust-analyzer"],
+                    "settings": { "editor.formatOnSave": true }
+                },
+                "jetbrains": {
+                    "backend": "IntelliJ",
+                    "plugins": ["com.intellij.plugins.rust"]
+                },
+                "codespaces": {
+                    "openFiles": ["README.md"],
+                    "disableAutomaticConfiguration": true
+                }
+            }
+        }"#;
+
+        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
+        let typed = devcontainer.customizations_typed();
+
+        let vscode = typed.vscode.unwrap();
+        assert_eq!(
+            vscode.extensions,
+            Some(vec!["rust-lang.rust-analyzer".to_string()])
+        );
+        assert_eq!(
+            vscode.settings.unwrap()["editor.formatOnSave"],
+            serde_json::json!(true)
+        );
+
+        let jetbrains = typed.jetbrains.unwrap();
+        assert_eq!(jetbrains.backend, Some("IntelliJ".to_string()));
+        assert_eq!(jetbrains.plugins.unwrap().len(), 1);
+
+        let codespaces = typed.codespaces.unwrap();
+        assert_eq!(codespaces.open_files, Some(vec!["README.md".to_string()]));
+        assert!(codespaces
+            .additional_fields
+            .contains_key("disableAutomaticConfiguration"));
+
+        assert_eq!(
+            devcontainer
+                .vscode_customizations()
+                .unwrap()
+                .unwrap()
+                .extensions,
+            Some(vec!["rust-lang.rust-analyzer".to_string()])
+        );
+        assert_eq!(
+            DevContainer::default().customizations_typed(),
+            TypedCustomizations::default()
+        );
+    }
+
+    #[test]
+    fn test_port_spec_host_vs_service() {
+        let json = r#"{
+            "image": "node:18",
+            "forwardPorts": ["db:5432", "localhost:3000", "127.0.0.1:8080"]
+        }"#;
+
+        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
+        let ports = devcontainer.forward_ports.as_ref().unwrap();
+        assert_eq!(
+            ports[0],
+            PortSpec::Service(ServicePort {
+                service: "db".to_string(),
+                port: 5432,
+                end: None,
+            })
+        );
+        assert_eq!(
+            ports[1],
+            PortSpec::Host(HostPort {
+                host: "localhost".to_string(),
+                port: 3000
+            })
+        );
+        assert!(matches!(&ports[2], PortSpec::Host(host) if host.host == "127.0.0.1"));
+        assert_eq!(ServicePort::parse("localhost:3000"), None);
+        assert_eq!(HostPort::parse("db:5432"), None);
+
+        let json = serde_json::to_string(&devcontainer.forward_ports).unwrap();
+        assert_eq!(json, r#"["db:5432","localhost:3000","127.0.0.1:8080"]"#);
+        assert_eq!(
+            devcontainer.all_ports().collect::<Vec<_>>(),
+            vec![3000, 8080]
+        );
+    }
+
+    #[test]
+    fn test_command_count() {
+        let single: DevContainer =
+            serde_json::from_str(r#"{ "image": "node:18", "postCreateCommand": "npm install" }"#)
+                .unwrap();
+        assert_eq!(single.command_count(), 1);
+
+        let parallel: DevContainer = serde_json::from_str(
+            r#"{
+                "image": "node:18",
+                "postStartCommand": {
+                    "server": "npm start",
+                    "watch": "npm run watch",
+                    "db": ["docker", "compose", "up", "db"]
+                }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(parallel.command_count(), 3);
+
+        assert_eq!(DevContainer::default().command_count(), 0);
+    }
+
+    #[test]
+    fn test_effective_dockerfile_precedence() {
+        let legacy: DevContainer =
+            serde_json::from_str(r#"{ "dockerFile": "Dockerfile" }"#).unwrap();
+        assert_eq!(legacy.effective_dockerfile(), Some("Dockerfile"));
+        assert!(legacy.lint().is_empty());
+
+        let both: DevContainer = serde_json::from_str(
+            r#"{
+                "dockerFile": "Dockerfile",
+                "build": { "dockerfile": "Dockerfile.dev" }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(both.effective_dockerfile(), Some("Dockerfile.dev"));
+        assert_eq!(DevContainer::default().effective_dockerfile(), None);
+    }
+
+    #[test]
+    fn test_lint_dockerfile_conflict() {
+        let conflict: DevContainer = serde_json::from_str(
+            r#"{
+                "dockerFile": "Dockerfile",
+                "build": { "dockerfile": "Dockerfile.dev" }
+            }"#,
+        )
+        .unwrap();
+        let diagnostics = conflict.lint();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].field, "dockerFile");
+
+        let same: DevContainer = serde_json::from_str(
+            r#"{
+                "dockerFile": "Dockerfile",
+                "build": { "dockerfile": "Dockerfile" }
+            }"#,
+        )
+        .unwrap();
+        assert!(same.lint().is_empty());
+    }
+
+    #[test]
+    fn test_set_and_remove_customization() {
+        let mut devcontainer = DevContainer::default();
+        devcontainer.set_customization(
+            "vscode",
+            serde_json::json!({ "extensions": ["rust-lang.rust-analyzer"] }),
+        );
+        assert_eq!(
+            devcontainer.customizations.as_ref().unwrap()["vscode"]["extensions"][0],
+            "rust-lang.rust-analyzer"
+        );
+
+        devcontainer.set_customization("vscode", serde_json::json!({ "extensions": [] }));
+        assert_eq!(
+            devcontainer.customizations.as_ref().unwrap()["vscode"],
+            serde_json::json!({ "extensions": [] })
+        );
+
+        assert_eq!(
+            devcontainer.remove_customization("vscode"),
+            Some(serde_json::json!({ "extensions": [] }))
+        );
+        assert_eq!(devcontainer.customizations, None);
+        assert_eq!(devcontainer.remove_customization("vscode"), None);
+    }
+
+    #[test]
+    fn test_invalid_port_attribute_keys() {
+        let json = r#"{
+            "image": "node:18",
+            "portsAttributes": {
+                "3000": { "label": "App" },
+                "3000-": { "label": "Broken range" },
+                "abc": { "label": "Not a port" },
+                "40000-55000": { "onAutoForward": "ignore" },
+                ".+/server.js": { "label": "Server" }
+            }
+        }"#;
+
+        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            devcontainer.invalid_port_attribute_keys(),
+            vec!["3000-".to_string(), "abc".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_invalid_port_attribute_keys_all_valid() {
+        let json = r#"{
+            "image": "node:18",
+            "portsAttributes": {
+                "3000": { "label": "App" },
+                "9000-9010": { "label": "Workers" }
+            }
+        }"#;
+
+        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
+        assert!(devcontainer.invalid_port_attribute_keys().is_empty());
+    }
+
+    #[test]
+    fn test_lifecycle_command_merge_objects() {
+        let base = LifecycleCommand::Object(BTreeMap::from([
+            (
+                "install".to_string(),
+                CommandSpec::Shell("npm ci".to_string()),
+            ),
+            (
+                "build".to_string(),
+                CommandSpec::Shell("npm run build".to_string()),
+            ),
+        ]));
+        let other = LifecycleCommand::Object(BTreeMap::from([
+            (
+                "build".to_string(),
+                CommandSpec::Shell("npm run build:dev".to_string()),
+            ),
+            (
+                "lint".to_string(),
+                CommandSpec::Shell("npm run lint".to_string()),
+            ),
+        ]));
+
+        assert_eq!(
+            base.merge(&other),
+            LifecycleCommand::Object(BTreeMap::from([
+                (
+                    "build".to_string(),
+                    CommandSpec::Shell("npm run build:dev".to_string())
+                ),
+                (
+                    "install".to_string(),
+                    CommandSpec::Shell("npm ci".to_string())
+                ),
+                (
+                    "lint".to_string(),
+                    CommandSpec::Shell("npm run lint".to_string())
+                ),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_lifecycle_command_merge_promotes_single_command() {
+        let base = LifecycleCommand::Command(CommandSpec::Args(vec![
+            "cargo".to_string(),
+            "fetch".to_string(),
+        ]));
+        let other = LifecycleCommand::Object(BTreeMap::from([(
+            "tools".to_string(),
+            CommandSpec::Shell("cargo install just".to_string()),
+        )]));
+
+        assert_eq!(
+            base.merge(&other),
+            LifecycleCommand::Object(BTreeMap::from([
+                (
+                    "base".to_string(),
+                    CommandSpec::Args(vec!["cargo".to_string(), "fetch".to_string()])
+                ),
+                (
+                    "tools".to_string(),
+                    CommandSpec::Shell("cargo install just".to_string())
+                ),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_cache_from_string_or_array() {
+        let single: DevContainer = serde_json::from_str(
+            r#"{ "build": { "dockerfile": "Dockerfile", "cacheFrom": "ghcr.io/org/app:cache" } }"#,
+        )
+        .unwrap();
+        let array: DevContainer = serde_json::from_str(
+            r#"{ "build": { "dockerfile": "Dockerfile", "cacheFrom": ["ghcr.io/org/app:cache"] } }"#,
+        )
+        .unwrap();
+
+        let expected = Some(vec!["ghcr.io/org/app:cache".to_string()]);
+        assert_eq!(single.build.as_ref().unwrap().cache_from, expected);
+        assert_eq!(array.build.as_ref().unwrap().cache_from, expected);
+
+        let absent: DevContainer =
+            serde_json::from_str(r#"{ "build": { "dockerfile": "Dockerfile" } }"#).unwrap();
+        assert_eq!(absent.build.unwrap().cache_from, None);
+    }
+
+    #[test]
+    fn test_sanitize_for_logging() {
+        let json = r#"{
+            "image": "ubuntu:latest",
+            "containerEnv": {
+                "GITHUB_TOKEN": "ghp_abc",
+                "db_password": "hunter2",
+                "NODE_ENV": "development"
+            },
+            "remoteEnv": {
+                "AWS_SECRET_ACCESS_KEY": "xyz",
+                "EDITOR": "vim"
+            }
+        }"#;
+
+        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
+        let sanitized = devcontainer.sanitize_for_logging();
+
+        let container_env = sanitized.container_env.unwrap();
+        assert_eq!(container_env["GITHUB_TOKEN"], REDACTED);
+        assert_eq!(container_env["db_password"], REDACTED);
+        assert_eq!(container_env["NODE_ENV"], "development");
+
+        let remote_env = sanitized.remote_env.unwrap();
+        assert_eq!(remote_env["AWS_SECRET_ACCESS_KEY"], REDACTED);
+        assert_eq!(remote_env["EDITOR"], "vim");
+
+        assert_eq!(
+            devcontainer.container_env.unwrap()["GITHUB_TOKEN"],
+            "ghp_abc"
+        );
+    }
+
+    #[test]
+    fn test_port_attributes_opens_browser() {
+        let cases = [
+            ("notify", OnAutoForward::Notify, false),
+            ("openBrowser", OnAutoForward::OpenBrowser, true),
+            ("openBrowserOnce", OnAutoForward::OpenBrowserOnce, true),
+            ("openPreview", OnAutoForward::OpenPreview, true),
+            ("silent", OnAutoForward::Silent, false),
+            ("ignore", OnAutoForward::Ignore, false),
+        ];
+        for (name, action, opens_browser) in cases {
+            let json = alloc::format!(r#"{{ "onAutoForward": "{}" }}"#, name);
+            let attributes: PortAttributes = serde_json::from_str(&json).unwrap();
+            assert_eq!(attributes.on_auto_forward, Some(action));
+            assert_eq!(attributes.opens_browser(), opens_browser, "{}", name);
+        }
+        assert!(!PortAttributes::default().opens_browser());
+
+        let other: PortAttributes =
+            serde_json::from_str(r#"{ "onAutoForward": "openInBrowserTab" }"#).unwrap();
+        assert_eq!(
+            other.on_auto_forward,
+            Some(OnAutoForward::Other("openInBrowserTab".to_string()))
+        );
+        assert!(!other.opens_browser());
+        assert_eq!(
+            serde_json::to_string(&other).unwrap(),
+            r#"{"onAutoForward":"openInBrowserTab"}"#
+        );
+    }
+
+    #[test]
+    fn test_feature_version_satisfies() {
+        let feature = FeatureRef::parse("ghcr.io/devcontainers/features/node:2").unwrap();
+        assert!(!feature.version_satisfies("3"));
+        assert!(feature.version_satisfies("2"));
+        assert!(feature.version_satisfies("2.1"));
+        assert!(feature.version_satisfies("1.9"));
+
+        let feature = FeatureRef::parse("ghcr.io/devcontainers/features/node:2.0.3").unwrap();
+        assert!(!feature.version_satisfies("2.1"));
+        assert!(feature.version_satisfies("2.0"));
+
+        let feature = FeatureRef::parse("ghcr.io/devcontainers/features/node").unwrap();
+        assert!(feature.version_satisfies("3"));
+
+        let feature = FeatureRef::parse("ghcr.io/devcontainers/features/node:lts").unwrap();
+        assert!(feature.version_satisfies("lts"));
+        assert!(!feature.version_satisfies("3"));
+    }
+
+    #[test]
+    fn test_features_below() {
+        let json = r#"{
+            "image": "ubuntu:latest",
+            "features": {
+                "ghcr.io/devcontainers/features/node:2": {},
+                "ghcr.io/devcontainers/features/go:1.22": {},
+                "ghcr.io/devcontainers/features/rust:1": {}
+            }
+        }"#;
+
+        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
+        let min_versions = BTreeMap::from([
+            (
+                "ghcr.io/devcontainers/features/node".to_string(),
+                "3".to_string(),
+            ),
+            (
+                "ghcr.io/devcontainers/features/go".to_string(),
+                "1.22".to_string(),
+            ),
+        ]);
+        assert_eq!(
+            devcontainer.features_below(&min_versions),
+            vec!["ghcr.io/devcontainers/features/node:2".to_string()]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "docker-compose")]
+    fn test_to_compose_override() {
+        let json = r#"{
+            "dockerComposeFile": "docker-compose.yml",
+            "service": "app",
+            "containerUser": "node",
+            "containerEnv": { "NODE_ENV": "development" },
+            "forwardPorts": [3000, "db:5432"],
+            "appPort": 9229
+        }"#;
+
+        #[cfg(not(feature = "ordered-env"))]
+        let environment = serde_json::json!({ "NODE_ENV": "development" });
+        #[cfg(feature = "ordered-env")]
+        let environment = serde_json::json!(["NODE_ENV=development"]);
+
+        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            devcontainer.to_compose_override(),
+            Some(serde_json::json!({
+                "services": {
+                    "app": {
+                        "environment": environment,
+                        "ports": ["3000:3000", "9229:9229"],
+                        "user": "node"
+                    }
+                }
+            }))
+        );
+        assert_eq!(DevContainer::default().to_compose_override(), None);
+    }
+
+    #[test]
+    fn test_lint_wait_for_configured_command() {
+        let json = r#"{
+            "image": "node:18",
+            "postCreateCommand": "npm install",
+            "waitFor": "postCreateCommand"
+        }"#;
+
+        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            devcontainer.wait_for,
+            Some(WaitFor::Phase(LifecyclePhase::PostCreate))
+        );
+        assert!(devcontainer.lint().is_empty());
+    }
+
+    #[test]
+    fn test_lint_wait_for_missing_command() {
+        let json = r#"{
+            "image": "node:18",
+            "postCreateCommand": "npm install",
+            "waitFor": "updateContentCommand"
+        }"#;
+
+        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
+        let diagnostics = devcontainer.lint();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+        assert_eq!(diagnostics[0].field, "waitFor");
+    }
+
+    #[test]
+    fn test_env_payload_bytes() {
+        let json = r#"{
+            "image": "ubuntu:latest",
+            "containerEnv": { "A": "1", "HOME": "/root" },
+            "remoteEnv": { "EDITOR": "vim" }
+        }"#;
+
+        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
+        // "A=1" + "HOME=/root" + "EDITOR=vim"
+        assert_eq!(devcontainer.env_payload_bytes(), 3 + 10 + 10);
+        assert_eq!(DevContainer::default().env_payload_bytes(), 0);
+    }
+
+    #[test]
+    fn test_check_env_payload_limit() {
+        let json = r#"{
+            "image": "ubuntu:latest",
+            "containerEnv": { "A": "1", "HOME": "/root" }
+        }"#;
+
+        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
+        assert_eq!(devcontainer.check_env_payload(13), None);
+        let diagnostic = devcontainer.check_env_payload(12).unwrap();
+        assert_eq!(diagnostic.severity, Severity::Error);
+        assert_eq!(diagnostic.field, "containerEnv");
+    }
+
+    #[test]
+    fn test_validate_absolute_mount_targets() {
+        let json = r#"{
+            "image": "ubuntu:latest",
+            "mounts": [
+                "source=cache,target=/cache,type=volume",
+                { "source": "${localWorkspaceFolder}", "target": "${containerWorkspaceFolder}", "type": "bind" }
+            ]
+        }"#;
+
+        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
+        assert!(devcontainer.lint().is_empty());
+        assert_eq!(devcontainer.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_relative_mount_target() {
+        let json = r#"{
+            "image": "ubuntu:latest",
+            "mounts": [
+                "source=cache,target=cache,type=volume",
+                { "source": "data", "target": "data", "type": "volume" }
+            ]
+        }"#;
+
+        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
+        let errors = devcontainer.validate().unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert!(errors
+            .iter()
+            .all(|error| error.severity == Severity::Error && error.field == "mounts"));
+    }
+
+    #[test]
+    fn test_with_overlay_env() {
+        let json = r#"{
+            "image": "ubuntu:latest",
+            "containerEnv": { "CI": "false", "NODE_ENV": "development" }
+        }"#;
+
+        let mut devcontainer: DevContainer = serde_json::from_str(json).unwrap();
+        devcontainer
+            .with_overlay_env(BTreeMap::from([
+                ("CI".to_string(), "true".to_string()),
+                ("BUILD_ID".to_string(), "42".to_string()),
+            ]))
+            .with_overlay_env(BTreeMap::new());
+
+        let env = devcontainer.container_env.unwrap();
+        assert_eq!(env.len(), 3);
+        assert_eq!(env["CI"], "true");
+        assert_eq!(env["BUILD_ID"], "42");
+        assert_eq!(env["NODE_ENV"], "development");
+
+        let mut empty = DevContainer::default();
+        empty.with_overlay_env(BTreeMap::from([("CI".to_string(), "true".to_string())]));
+        assert_eq!(empty.container_env.unwrap()["CI"], "true");
+    }
+
+    #[test]
+    fn test_resolved_name_basename() {
+        let json = r#"{
+            "name": "${localWorkspaceFolderBasename} dev",
+            "image": "ubuntu:latest"
+        }"#;
+
+        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
+        let ctx = SubstitutionContext {
+            local_workspace_folder: Some("/home/me/projects/devil/".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            devcontainer.resolved_name(&ctx),
+            Some("devil dev".to_string())
+        );
+        assert_eq!(
+            devcontainer.substituted(&ctx).name,
+            Some("devil dev".to_string())
+        );
+
+        let windows = SubstitutionContext {
+            local_workspace_folder: Some("C:\\Users\\me\\devil".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            devcontainer.resolved_name(&windows),
+            Some("devil dev".to_string())
+        );
+        assert_eq!(DevContainer::default().resolved_name(&ctx), None);
+    }
+
+    #[test]
+    fn test_lint_feature_install_order_unknown_feature() {
+        let json = r#"{
+            "image": "ubuntu:latest",
+            "features": {
+                "ghcr.io/devcontainers/features/common-utils:2": {},
+                "ghcr.io/devcontainers/features/node:1": {}
+            },
+            "overrideFeatureInstallOrder": [
+                "ghcr.io/devcontainers/features/common-utils",
+                "ghcr.io/devcontainers/features/go"
+            ]
+        }"#;
+
+        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
+        let errors = devcontainer.validate().unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "overrideFeatureInstallOrder");
+        assert!(errors[0]
+            .message
+            .contains("ghcr.io/devcontainers/features/go"));
+    }
+
+    #[test]
+    fn test_lint_feature_install_order_duplicate_entry() {
+        let json = r#"{
+            "image": "ubuntu:latest",
+            "features": {
+                "ghcr.io/devcontainers/features/node:1": {}
+            },
+            "overrideFeatureInstallOrder": [
+                "ghcr.io/devcontainers/features/node",
+                "ghcr.io/devcontainers/features/node:1"
+            ]
+        }"#;
+
+        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
+        let errors = devcontainer.validate().unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].message.contains("more than once"));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_from_reader() {
+        let json = br#"{ "name": "From Reader", "image": "ubuntu:latest" }"#;
+        let devcontainer = DevContainer::from_reader(std::io::Cursor::new(&json[..])).unwrap();
+        assert_eq!(devcontainer.name, Some("From Reader".to_string()));
+
+        let err = DevContainer::from_reader(std::io::Cursor::new(&b"{"[..])).unwrap_err();
+        assert!(matches!(err, ParseError::Json(_)));
+    }
+
+    #[test]
+    fn test_port_spec_same_port() {
+        let ports: Vec<PortSpec> =
+            serde_json::from_str(r#"[3000, "3000", "localhost:3000", "3001", "db:3000"]"#).unwrap();
+        assert_ne!(ports[0], ports[1]);
+        assert!(ports[0].same_port(&ports[1]));
+        assert!(ports[1].same_port(&ports[0]));
+        assert!(ports[0].same_port(&ports[2]));
+        assert!(!ports[0].same_port(&ports[3]));
+        assert!(!ports[0].same_port(&ports[4]));
+        assert!(ports[4].same_port(&ports[4]));
+
+        // The string form is preserved on serialization
+        assert_eq!(
+            serde_json::to_string(&ports[..2]).unwrap(),
+            r#"[3000,"3000"]"#
+        );
+    }
+
+    #[test]
+    fn test_validate_customization_required_keys() {
+        let config: DevContainer = serde_json::from_str(
+            r#"{
+                "customizations": {
+                    "mytool": { "team": "infra", "tier": 1 },
+                    "other": { "tier": 2 }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        assert!(config
+            .validate_customization_required_keys("mytool", &["team", "tier"])
+            .is_empty());
+        assert_eq!(
+            config.validate_customization_required_keys("other", &["team", "tier"]),
+            vec!["team".to_string()]
+        );
+        assert_eq!(
+            config.validate_customization_required_keys("missing", &["team"]),
+            vec!["team".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_strip_jsonc_comments() {
+        let input = r#"{
+    // line comment
+    "name": "a // not a comment", /* block
+    spanning lines */ "image": "b /* kept */ \" // still a string"
+}"#;
+        let stripped = strip_jsonc_comments(input);
+        assert!(!stripped.contains("line comment"));
+        assert!(!stripped.contains("spanning"));
+        assert_eq!(stripped.lines().count(), input.lines().count());
+
+        let config = DevContainer::from_jsonc(input).unwrap();
+        assert_eq!(config.name, Some("a // not a comment".to_string()));
+        assert_eq!(
+            config.image,
+            Some("b /* kept */ \" // still a string".to_string())
+        );
+    }
+
+    #[test]
+    fn test_shutdown_action_predicates() {
+        assert!(!ShutdownAction::None.stops_container());
+        assert!(!ShutdownAction::None.stops_compose());
+        assert!(ShutdownAction::StopContainer.stops_container());
+        assert!(!ShutdownAction::StopContainer.stops_compose());
+        #[cfg(feature = "docker-compose")]
+        {
+            assert!(!ShutdownAction::StopCompose.stops_container());
+            assert!(ShutdownAction::StopCompose.stops_compose());
+        }
+    }
+
+    #[test]
+    fn test_feature_option() {
+        let config: DevContainer = serde_json::from_str(
+            r#"{
+                "features": {
+                    "ghcr.io/devcontainers/features/node:1": {
+                        "version": "20",
+                        "nodeGypDependencies": false
+                    },
+                    "ghcr.io/devcontainers/features/git:1": "latest"
+                }
+            }"#,
+        )
+        .unwrap();
+        let node = "ghcr.io/devcontainers/features/node:1";
+
+        let version: String = config.feature_option(node, "version").unwrap().unwrap();
+        assert_eq!(version, "20");
+        let gyp: bool = config
+            .feature_option(node, "nodeGypDependencies")
+            .unwrap()
+            .unwrap();
+        assert!(!gyp);
+        assert!(config
+            .feature_option::<bool>(node, "version")
+            .unwrap()
+            .is_err());
+        assert!(config.feature_option::<String>(node, "missing").is_none());
+        assert!(config
+            .feature_option::<String>("ghcr.io/devcontainers/features/git:1", "version")
+            .is_none());
+    }
+
+    #[test]
+    fn test_build_effective_context() {
+        let build = BuildConfig {
+            dockerfile: Some("Dockerfile".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(build.effective_context(), ".");
+
+        let build = BuildConfig {
+            context: Some("..".to_string()),
+            ..build
+        };
+        assert_eq!(build.effective_context(), "..");
+    }
+
+    #[test]
+    fn test_mount_type() {
+        for (name, mount_type) in [
+            ("bind", MountType::Bind),
+            ("volume", MountType::Volume),
+            ("tmpfs", MountType::Tmpfs),
+            ("npipe", MountType::Other("npipe".to_string())),
+        ] {
+            let mount = MountSpec::parse(&alloc::format!("type={},target=/x", name)).unwrap();
+            assert_eq!(mount.mount_type, Some(mount_type.clone()));
+
+            let json = alloc::format!(r#"{{"type":"{}","target":"/x"}}"#, name);
+            let spec: MountSpec = serde_json::from_str(&json).unwrap();
+            assert_eq!(spec, mount);
+            assert_eq!(
+                serde_json::to_string(&spec).unwrap(),
+                alloc::format!(r#"{{"target":"/x","type":"{}"}}"#, name)
+            );
+        }
+    }
+
+    #[test]
+    fn test_auto_forward_summary() {
+        let config: DevContainer = serde_json::from_str(
+            r#"{
+                "forwardPorts": [3000, 8080, 9000, "3000", "db:5432"],
+                "portsAttributes": {
+                    "3000": { "onAutoForward": "silent" },
+                    "9000-9100": { "onAutoForward": "openBrowser" }
+                }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.auto_forward_summary(),
+            vec![
+                (3000, OnAutoForward::Silent),
+                (8080, OnAutoForward::Notify),
+                (9000, OnAutoForward::OpenBrowser),
+            ]
+        );
+
+        let config = DevContainer {
+            other_ports_attributes: Some(PortAttributes {
+                on_auto_forward: Some(OnAutoForward::Ignore),
+                ..Default::default()
+            }),
+            ..config
+        };
+        assert_eq!(
+            config.auto_forward_summary()[1],
+            (8080, OnAutoForward::Ignore)
+        );
+    }
+
+    #[test]
+    fn test_local_features() {
+        assert_eq!(
+            FeatureRef::parse("./local-features/my-feature"),
+            Some(FeatureRef::Local {
+                path: "./local-features/my-feature".to_string()
+            })
+        );
+        let parent = FeatureRef::parse("../shared/feature").unwrap();
+        assert_eq!(parent.canonical(), "../shared/feature");
+        assert!(!parent.version_satisfies("1"));
+
+        let config: DevContainer = serde_json::from_str(
+            r#"{
+                "features": {
+                    "./local-features/my-feature": {},
+                    "../shared/feature": {},
+                    "ghcr.io/devcontainers/features/node:1": {}
+                }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.local_features(),
+            vec!["../shared/feature", "./local-features/my-feature"]
+        );
+    }
+
+    #[test]
+    fn test_env_overlaps() {
+        let config: DevContainer = serde_json::from_str(
+            r#"{
+                "containerEnv": { "PATH": "/usr/bin", "LANG": "C.UTF-8" },
+                "remoteEnv": { "PATH": "${containerEnv:PATH}:/opt/bin", "EDITOR": "vim" }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(config.env_overlaps(), vec!["PATH".to_string()]);
+        assert!(DevContainer::default().env_overlaps().is_empty());
+    }
+
+    #[test]
+    fn test_command_predicates() {
+        let shell = CommandSpec::Shell("npm install".to_string());
+        let args = CommandSpec::Args(vec!["npm".to_string(), "install".to_string()]);
+        assert!(shell.is_shell());
+        assert!(!args.is_shell());
+
+        assert!(!LifecycleCommand::Command(shell.clone()).is_parallel());
+        assert!(!LifecycleCommand::Command(args.clone()).is_parallel());
+        let parallel = LifecycleCommand::Object(BTreeMap::from([
+            ("a".to_string(), shell),
+            ("b".to_string(), args),
+        ]));
+        assert!(parallel.is_parallel());
+    }
+
+    #[test]
+    fn test_to_json_value_redacted() {
+        let config: DevContainer = serde_json::from_str(
+            r#"{
+                "containerEnv": { "NPM_AUTH": "abc", "LANG": "C.UTF-8" },
+                "remoteEnv": { "GITHUB_TOKEN": "ghp_x" },
+                "secrets": {
+                    "NPM_AUTH": {
+                        "description": "npm registry credentials",
+                        "documentationUrl": "https://example.com/npm"
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let value = config.to_json_value_redacted(&["auth"]);
+        assert_eq!(value["containerEnv"]["NPM_AUTH"], REDACTED);
+        assert_eq!(value["containerEnv"]["LANG"], "C.UTF-8");
+        assert_eq!(value["remoteEnv"]["GITHUB_TOKEN"], "ghp_x");
+        assert_eq!(
+            value["secrets"]["NPM_AUTH"],
+            serde_json::json!({
+                "description": "npm registry credentials",
+                "documentationUrl": "https://example.com/npm"
+            })
+        );
+    }
+
+    #[test]
+    fn test_redundant_app_ports() {
+        let config: DevContainer = serde_json::from_str(
+            r#"{
+                "forwardPorts": [3000, 5000, "7000-7002"],
+                "appPort": [3000, 8080, "7001"]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(config.redundant_app_ports(), vec![3000, 7001]);
+
+        let config = DevContainer {
+            app_port: Some(AppPort::Single(PortSpec::Number(8080))),
+            ..config
+        };
+        assert!(config.redundant_app_ports().is_empty());
+    }
+
+    #[cfg(feature = "vscode")]
+    #[test]
+    fn test_all_extensions() {
+        let legacy: DevContainer =
+            serde_json::from_str(r#"{ "extensions": ["rust-lang.rust-analyzer"] }"#).unwrap();
+        assert_eq!(legacy.all_extensions(), vec!["rust-lang.rust-analyzer"]);
+
+        let modern: DevContainer = serde_json::from_str(
+            r#"{ "customizations": { "vscode": { "extensions": ["tamasfe.even-better-toml"] } } }"#,
+        )
+        .unwrap();
+        assert_eq!(modern.all_extensions(), vec!["tamasfe.even-better-toml"]);
+
+        let both = DevContainer {
+            extensions: Some(vec![
+                "rust-lang.rust-analyzer".to_string(),
+                "tamasfe.even-better-toml".to_string(),
+            ]),
+            ..modern
+        };
+        assert_eq!(
+            both.all_extensions(),
+            vec!["rust-lang.rust-analyzer", "tamasfe.even-better-toml"]
+        );
+    }
+
+    #[test]
+    fn test_build_labels_and_network() {
+        let json = r#"{"build":{"dockerfile":"Dockerfile","labels":{"org.opencontainers.image.source":"https://example.com/repo"},"network":"host"}}"#;
+        let config: DevContainer = serde_json::from_str(json).unwrap();
+        let build = config.build.as_ref().unwrap();
+        assert_eq!(build.network.as_deref(), Some("host"));
+        assert_eq!(
+            build.labels.as_ref().unwrap()["org.opencontainers.image.source"],
+            "https://example.com/repo"
+        );
+        assert_eq!(serde_json::to_string(&config).unwrap(), json);
+    }
+
+    #[test]
+    fn test_collection_counts() {
+        let empty = DevContainer::default();
+        assert_eq!(empty.feature_count(), 0);
+        assert!(!empty.has_features());
+        assert_eq!(empty.forwarded_port_count(), 0);
+        assert_eq!(empty.mount_count(), 0);
+
+        let config: DevContainer = serde_json::from_str(
+            r#"{
+                "features": { "ghcr.io/devcontainers/features/node:1": {} },
+                "forwardPorts": [3000, "8000-8010"],
+                "mounts": ["source=a,target=/a", { "source": "b", "target": "/b" }, "source=c,target=/c"]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(config.feature_count(), 1);
+        assert!(config.has_features());
+        assert_eq!(config.forwarded_port_count(), 2);
+        assert_eq!(config.mount_count(), 3);
+    }
+
+    #[test]
+    fn test_duplicate_extensions() {
+        let vscode: VsCodeCustomizations = serde_json::from_value(serde_json::json!({
+            "extensions": ["publisher.ext", "other.ext", "Publisher.Ext", "PUBLISHER.EXT"]
+        }))
+        .unwrap();
+        assert_eq!(vscode.duplicate_extensions(), vec!["publisher.ext"]);
+        assert!(VsCodeCustomizations::default()
+            .duplicate_extensions()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_lenient_bool_fields() {
+        let config: DevContainer = serde_json::from_str(
+            r#"{
+                "init": true,
+                "privileged": "true",
+                "overrideCommand": "false",
+                "updateRemoteUserUID": false
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(config.init, Some(true));
+        assert_eq!(config.privileged, Some(true));
+        assert_eq!(config.override_command, Some(false));
+        assert_eq!(config.update_remote_user_uid, Some(false));
+        assert_eq!(DevContainer::default().privileged, None);
+
+        // Booleans always serialize as JSON booleans
+        let json = serde_json::to_value(&config).unwrap();
+        assert_eq!(json["privileged"], true);
+
+        assert!(serde_json::from_str::<DevContainer>(r#"{"init": "yes"}"#).is_err());
+    }
+
+    #[cfg(feature = "docker-compose")]
+    #[test]
+    fn test_referenced_services() {
+        let config: DevContainer = serde_json::from_str(
+            r#"{
+                "dockerComposeFile": "docker-compose.yml",
+                "service": "app",
+                "forwardPorts": [3000, "db:5432", "cache:6379", "app:8080", "db:5433"]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(config.referenced_services(), vec!["app", "db", "cache"]);
+    }
+
+    #[test]
+    fn test_port_attributes_unknown_values_roundtrip() {
+        let json = r#"{"protocol":"grpc","onAutoForward":"openInBrowserTab"}"#;
+        let attributes: PortAttributes = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            attributes.protocol,
+            Some(PortProtocol::Other("grpc".to_string()))
+        );
+        assert_eq!(
+            attributes.on_auto_forward,
+            Some(OnAutoForward::Other("openInBrowserTab".to_string()))
+        );
+        assert!(!attributes.opens_browser());
+        assert_eq!(serde_json::to_string(&attributes).unwrap(), json);
+
+        let known: PortAttributes = serde_json::from_str(r#"{"protocol":"https"}"#).unwrap();
+        assert_eq!(known.protocol, Some(PortProtocol::Https));
+        assert_eq!(
+            serde_json::to_string(&known).unwrap(),
+            r#"{"protocol":"https"}"#
+        );
+    }
+
+    #[test]
+    fn test_command_for_phase() {
+        let config: DevContainer = serde_json::from_str(
+            r#"{
+                "onCreateCommand": "on-create",
+                "updateContentCommand": "update-content",
+                "postCreateCommand": "post-create",
+                "postStartCommand": "post-start"
+            }"#,
+        )
+        .unwrap();
+        let expected = [
+            (LifecyclePhase::OnCreate, Some("on-create")),
+            (LifecyclePhase::UpdateContent, Some("update-content")),
+            (LifecyclePhase::PostCreate, Some("post-create")),
+            (LifecyclePhase::PostStart, Some("post-start")),
+            (LifecyclePhase::PostAttach, None),
+        ];
+        for (phase, command) in expected {
+            let found = config
+                .command_for_phase(phase)
+                .map(|command| match command {
+                    LifecycleCommand::Command(CommandSpec::Shell(shell)) => shell.as_str(),
+                    other => panic!("unexpected command {:?}", other),
+                });
+            assert_eq!(found, command, "{}", phase.as_str());
+        }
+    }
+
+    #[test]
+    fn test_run_args_string_form() {
+        let config: DevContainer = serde_json::from_str(
+            r#"{ "runArgs": "--cap-add=SYS_PTRACE  --label 'team=dev tools' --name=\"my box\"" }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.run_args,
+            Some(vec![
+                "--cap-add=SYS_PTRACE".to_string(),
+                "--label".to_string(),
+                "team=dev tools".to_string(),
+                "--name=my box".to_string(),
+            ])
+        );
+
+        let config: DevContainer =
+            serde_json::from_str(r#"{ "runArgs": ["--label", "team=dev tools"] }"#).unwrap();
+        assert_eq!(
+            config.run_args,
+            Some(vec!["--label".to_string(), "team=dev tools".to_string()])
+        );
+        assert_eq!(split_args("a '' b"), vec!["a", "", "b"]);
+    }
+
+    #[test]
+    fn test_feature_implied_privileged_and_init() {
+        let config: DevContainer = serde_json::from_str(
+            r#"{
+                "privileged": false,
+                "features": { "ghcr.io/devcontainers/features/docker-in-docker:2": {} }
+            }"#,
+        )
+        .unwrap();
+        assert!(config.implied_privileged());
+        assert!(config.effective_init());
+
+        let config: DevContainer = serde_json::from_str(
+            r#"{
+                "init": true,
+                "features": { "ghcr.io/devcontainers/features/node:1": {} }
+            }"#,
+        )
+        .unwrap();
+        assert!(!config.implied_privileged());
+        assert!(config.effective_init());
+        assert!(!DevContainer::default().effective_init());
+    }
+
+    #[test]
+    fn test_bind_mount_sources() {
+        let config: DevContainer = serde_json::from_str(
+            r#"{
+                "mounts": [
+                    "source=/var/run/docker.sock,target=/var/run/docker.sock,type=bind",
+                    "source=node_modules,target=/workspace/node_modules,type=volume",
+                    "source=${localEnv:HOME},target=/home/host,type=bind",
+                    { "source": "${localEnv:HOME}/.ssh", "target": "/root/.ssh", "type": "bind" }
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.bind_mount_sources(),
+            vec!["/var/run/docker.sock", "${localEnv:HOME}/.ssh"]
+        );
+    }
+
+    #[test]
+    fn test_feature_ref_display_roundtrip() {
+        for key in [
+            "ghcr.io/devcontainers/features/node:1",
+            "localhost:5000/team/features/tool",
+            "ghcr.io/devcontainers/features/go@sha256:abc123",
+            "./local-features/my-feature",
+            "https://example.com/feature.tgz",
+        ] {
+            let feature = FeatureRef::parse(key).unwrap();
+            assert_eq!(feature.to_string(), key);
+            assert_eq!(FeatureRef::parse(&feature.to_string()), Some(feature));
+        }
+    }
+
+    #[test]
+    fn test_collect_variables() {
+        let config: DevContainer = serde_json::from_str(
+            r#"{
+                "name": "${localWorkspaceFolderBasename}",
+                "workspaceMount": "source=${localWorkspaceFolder},target=/workspace,type=bind",
+                "containerEnv": {
+                    "FOO": "${localEnv:FOO}",
+                    "FOO_OR_DEFAULT": "${localEnv:FOO:fallback}",
+                    "ID": "${devcontainerId}"
+                },
+                "remoteEnv": { "PATH": "${containerEnv:PATH}:/opt/bin" }
+            }"#,
+        )
+        .unwrap();
+        let expected: BTreeSet<String> = [
+            "containerEnv:PATH",
+            "devcontainerId",
+            "localEnv:FOO",
+            "localWorkspaceFolder",
+            "localWorkspaceFolderBasename",
+        ]
+        .into_iter()
+        .map(String::from)
+        .collect();
+        assert_eq!(config.collect_variables(), expected);
+    }
+
+    #[cfg(feature = "ordered-env")]
+    #[test]
+    fn test_ordered_env_preserves_file_order() {
+        let json = r#"{"containerEnv":{"FOO":"1","ABC":"2","BAR":"3"}}"#;
+        let config: DevContainer = serde_json::from_str(json).unwrap();
+        let env = config.container_env.as_ref().unwrap();
+        let keys: Vec<&str> = env.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(keys, vec!["FOO", "ABC", "BAR"]);
+        assert_eq!(env["ABC"], "2");
+        assert_eq!(serde_json::to_string(&config).unwrap(), json);
+
+        let mut env = env.clone();
+        assert_eq!(
+            env.insert("ABC".to_string(), "4".to_string()),
+            Some("2".to_string())
+        );
+        env.insert("ZED".to_string(), "5".to_string());
+        let keys: Vec<&str> = env.keys().map(String::as_str).collect();
+        assert_eq!(keys, vec!["FOO", "ABC", "BAR", "ZED"]);
+    }
+
+    #[test]
+    fn test_as_features_only() {
+        let config: DevContainer = serde_json::from_str(
+            r#"{
+                "name": "full",
+                "image": "ubuntu:latest",
+                "forwardPorts": [3000],
+                "features": {
+                    "ghcr.io/devcontainers/features/node:1": { "version": "20" },
+                    "ghcr.io/devcontainers/features/git:1": {}
+                },
+                "overrideFeatureInstallOrder": ["ghcr.io/devcontainers/features/git"]
+            }"#,
+        )
+        .unwrap();
+
+        let manifest = config.as_features_only();
+        assert_eq!(manifest.features, config.features);
+        assert_eq!(
+            manifest.override_feature_install_order,
+            config.override_feature_install_order
+        );
+        let value = serde_json::to_value(&manifest).unwrap();
+        let keys: Vec<&str> = value
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        assert_eq!(keys, vec!["features", "overrideFeatureInstallOrder"]);
+    }
+
+    #[test]
+    fn test_top_level_docker_file_roundtrip() {
+        let json = r#"{"name":"legacy","dockerFile":"Dockerfile"}"#;
+        let config: DevContainer = serde_json::from_str(json).unwrap();
+        assert_eq!(config.build, None);
+        assert_eq!(config.effective_dockerfile(), Some("Dockerfile"));
+
+        assert_eq!(serde_json::to_string(&config).unwrap(), json);
+        let minimal: serde_json::Value = serde_json::from_str(&config.to_minimal_json()).unwrap();
+        assert_eq!(
+            minimal,
+            serde_json::json!({"name": "legacy", "dockerFile": "Dockerfile"})
+        );
+        assert_eq!(config.substituted(&SubstitutionContext::default()), config);
+    }
+
+    #[test]
+    fn test_port_attributes_requires_elevation() {
+        for (require_local_port, elevate_if_needed, expected) in [
+            (None, None, false),
+            (Some(true), None, false),
+            (None, Some(true), false),
+            (Some(true), Some(false), false),
+            (Some(false), Some(true), false),
+            (Some(true), Some(true), true),
+        ] {
+            let attributes = PortAttributes {
+                require_local_port,
+                elevate_if_needed,
+                ..Default::default()
+            };
+            assert_eq!(
+                attributes.requires_elevation(),
+                expected,
+                "{:?} {:?}",
+                require_local_port,
+                elevate_if_needed
+            );
+        }
+    }
+
+    #[test]
+    fn test_is_minimal() {
+        let minimal: DevContainer =
+            serde_json::from_str(r#"{ "name": "Rust", "image": "rust:1" }"#).unwrap();
+        assert!(minimal.is_minimal());
+
+        let dockerfile: DevContainer =
+            serde_json::from_str(r#"{ "build": { "dockerfile": "Dockerfile" } }"#).unwrap();
+        assert!(dockerfile.is_minimal());
+
+        let with_ports: DevContainer = serde_json::from_str(
+            r#"{ "name": "Rust", "image": "rust:1", "forwardPorts": [3000] }"#,
+        )
+        .unwrap();
+        assert!(!with_ports.is_minimal());
+
+        let name_only: DevContainer = serde_json::from_str(r#"{ "name": "Rust" }"#).unwrap();
+        assert!(!name_only.is_minimal());
+    }
+
+    #[test]
+    fn test_substitute_container_workspace_folder() {
+        let json = r#"{
+            "workspaceFolder": "/workspaces/${localWorkspaceFolderBasename}",
+            "containerEnv": { "PATH": "${containerEnv:PATH}:${containerWorkspaceFolder}/bin" }
+        }"#;
+        let config: DevContainer = serde_json::from_str(json).unwrap();
+        let ctx = SubstitutionContext {
+            local_workspace_folder: Some("/home/me/project".to_string()),
+            container_workspace_folder: Some("/workspace".to_string()),
+            ..Default::default()
+        };
+        let resolved = config.substituted(&ctx);
+        assert_eq!(
+            resolved.container_env.unwrap()["PATH"],
+            "${containerEnv:PATH}:/workspaces/project/bin"
+        );
+
+        // Without workspaceFolder the context value is used
+        let config = DevContainer {
+            workspace_folder: None,
+            ..config
+        };
+        let resolved = config.substituted(&ctx);
+        assert_eq!(
+            resolved.container_env.unwrap()["PATH"],
+            "${containerEnv:PATH}:/workspace/bin"
+        );
+    }
+
+    #[test]
+    fn test_merge_preserves_feature_options() {
+        let mut base: DevContainer = serde_json::from_str(
+            r#"{
+                "features": {
+                    "ghcr.io/devcontainers/features/node:1": { "version": "18", "nvmVersion": "0.39" },
+                    "ghcr.io/devcontainers/features/git:1": {}
+                }
+            }"#,
+        )
+        .unwrap();
+        let other: DevContainer = serde_json::from_str(
+            r#"{
+                "features": {
+                    "ghcr.io/devcontainers/features/node:1": { "version": "20", "installYarn": false }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        base.merge(&other);
+        let features = base.features.unwrap();
+        assert_eq!(
+            features["ghcr.io/devcontainers/features/node:1"],
+            serde_json::json!({ "version": "20", "nvmVersion": "0.39", "installYarn": false })
+        );
+        assert!(features.contains_key("ghcr.io/devcontainers/features/git:1"));
+    }
+
+    #[test]
+    fn test_visit_strings_mut() {
+        let mut config: DevContainer = serde_json::from_str(
+            r#"{
+                "name": "demo",
+                "image": "ubuntu",
+                "containerEnv": { "A": "value" },
+                "postCreateCommand": ["npm", "install"],
+                "postStartCommand": { "server": "npm start" },
+                "mounts": ["source=data,target=/data", { "source": "cache", "target": "/cache" }],
+                "runArgs": ["--init"]
+            }"#,
+        )
+        .unwrap();
+
+        let mut visited = 0;
+        config.visit_strings_mut(|value| {
+            *value = value.to_uppercase();
+            visited += 1;
+        });
+        assert_eq!(visited, 10);
+
+        let mut lowercase = Vec::new();
+        config.visit_strings_mut(|value| {
+            if value.chars().any(|c| c.is_lowercase()) {
+                lowercase.push(value.clone());
+            }
+        });
+        assert!(lowercase.is_empty(), "{:?}", lowercase);
+        assert_eq!(config.name.as_deref(), Some("DEMO"));
+        assert_eq!(config.container_env.unwrap()["A"], "VALUE");
+        assert_eq!(config.run_args, Some(vec!["--INIT".to_string()]));
+    }
+
+    #[test]
+    fn test_validate_image_reference() {
+        for image in [
+            "ubuntu",
+            "mcr.microsoft.com/devcontainers/rust:1-bookworm",
+            "${localEnv:IMAGE}",
+        ] {
+            let config = DevContainer {
+                image: Some(image.to_string()),
+                ..Default::default()
+            };
+            assert_eq!(config.validate_image_reference(), None, "{}", image);
+        }
+
+        for image in ["ubuntu::", ""] {
+            let config = DevContainer {
+                image: Some(image.to_string()),
+                ..Default::default()
+            };
+            let diagnostic = config.validate_image_reference().unwrap();
+            assert_eq!(diagnostic.severity, Severity::Error);
+            assert_eq!(diagnostic.field, "image");
+            assert!(config.lint().contains(&diagnostic));
+            assert!(config.validate().is_err());
+        }
+    }
+
+    #[test]
+    fn test_service_port_range() {
+        let single = ServicePort::parse("db:5432").unwrap();
+        assert_eq!(single.end, None);
+        assert_eq!(single.ports(), 5432..=5432);
+        assert_eq!(single.to_string(), "db:5432");
+
+        let range = ServicePort::parse("db:5432-5434").unwrap();
+        assert_eq!(range.service, "db");
+        assert_eq!(range.port, 5432);
+        assert_eq!(range.end, Some(5434));
+        assert_eq!(range.ports(), 5432..=5434);
+        assert_eq!(range.to_string(), "db:5432-5434");
+        assert_eq!(ServicePort::parse("db:5434-5432"), None);
+
+        let ports: Vec<PortSpec> = serde_json::from_str(r#"["db:5432", "db:5432-5434"]"#).unwrap();
+        assert_eq!(ports[1], PortSpec::Service(range));
+        assert_eq!(
+            serde_json::to_string(&ports).unwrap(),
+            r#"["db:5432","db:5432-5434"]"#
+        );
+    }
+
+    #[test]
+    fn test_count_privileged_capabilities() {
+        let config: DevContainer = serde_json::from_str(
+            r#"{
+                "privileged": true,
+                "capAdd": ["SYS_PTRACE", "NET_ADMIN"],
+                "securityOpt": ["seccomp=unconfined"]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.count_privileged_capabilities(),
+            PrivilegeReport {
+                privileged: true,
+                cap_add: 2,
+                security_opt: 1,
+            }
+        );
+        assert_eq!(
+            DevContainer::default().count_privileged_capabilities(),
+            PrivilegeReport::default()
+        );
+    }
+
+    #[test]
+    fn test_parse_with_bom_and_leading_whitespace() {
+        let json = "\u{FEFF}{ \"name\": \"bom\" }";
+        let config: DevContainer = json.parse().unwrap();
+        assert_eq!(config.name, Some("bom".to_string()));
+        assert_eq!(DevContainer::from_slice(json.as_bytes()).unwrap(), config);
+        assert_eq!(DevContainer::from_jsonc(json).unwrap(), config);
+        assert!(serde_json::from_str::<DevContainer>(json).is_err());
+
+        let json = "\n\n  \r\n// comment\n{ \"name\": \"blank\" }";
+        assert_eq!(
+            DevContainer::from_jsonc(json).unwrap().name,
+            Some("blank".to_string())
+        );
+        let json = "\u{FEFF}\n\n{ \"name\": \"blank\" }";
+        assert_eq!(
+            json.parse::<DevContainer>().unwrap().name,
+            Some("blank".to_string())
+        );
+        assert!(matches!(
+            "{".parse::<DevContainer>(),
+            Err(ParseError::Json(_))
+        ));
+    }
+
+    #[test]
+    fn test_split_lifecycle_parallel() {
+        let config: DevContainer = serde_json::from_str(
+            r#"{
+                "onCreateCommand": "make setup",
+                "postCreateCommand": { "npm": "npm install", "pip": ["pip", "install", "-e", "."] },
+                "postStartCommand": {}
+            }"#,
+        )
+        .unwrap();
+        let groups = config.split_lifecycle_parallel();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(
+            groups[0],
+            vec![(
+                "onCreateCommand".to_string(),
+                CommandSpec::Shell("make setup".to_string())
+            )]
+        );
+        let names: Vec<&str> = groups[1].iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["npm", "pip"]);
+        assert!(!groups[1][1].1.is_shell());
+    }
+
+    #[test]
+    fn test_forward_port_object_form() {
+        let json = r#"{
+            "forwardPorts": [{ "port": 3000, "label": "App" }, 8080, { "port": 9000 }],
+            "portsAttributes": { "8080": { "label": "Admin" } }
+        }"#;
+
+        let raw: DevContainer = serde_json::from_str(json).unwrap();
+        assert!(matches!(
+            &raw.forward_ports.as_ref().unwrap()[0],
+            PortSpec::Labeled(labeled) if labeled.port == 3000
+        ));
+        assert_eq!(raw.all_ports().collect::<Vec<_>>(), vec![3000, 8080, 9000]);
+
+        let config: DevContainer = json.parse().unwrap();
+        assert_eq!(
+            config.forward_ports,
+            Some(vec![
+                PortSpec::Number(3000),
+                PortSpec::Number(8080),
+                PortSpec::Number(9000),
+            ])
+        );
+        let attributes = config.ports_attributes.unwrap();
+        assert_eq!(attributes["3000"].label.as_deref(), Some("App"));
+        assert_eq!(attributes["8080"].label.as_deref(), Some("Admin"));
+        assert!(!attributes.contains_key("9000"));
+    }
+
+    #[test]
+    fn test_requires_docker_compose() {
+        let image: DevContainer = serde_json::from_str(r#"{ "image": "ubuntu" }"#).unwrap();
+        assert!(!image.requires_docker_compose());
+
+        #[cfg(feature = "docker-compose")]
+        {
+            let compose: DevContainer = serde_json::from_str(
+                r#"{ "dockerComposeFile": ["docker-compose.yml"], "service": "app" }"#,
+            )
+            .unwrap();
+            assert!(compose.requires_docker_compose());
+
+            let no_service = DevContainer {
+                service: None,
+                ..compose
+            };
+            assert!(!no_service.requires_docker_compose());
+        }
+    }
+
+    #[test]
+    fn test_feature_options_get_or() {
+        let config: DevContainer = serde_json::from_str(
+            r#"{
+                "features": {
+                    "ghcr.io/devcontainers/features/node:1": { "version": "20", "installYarn": "no" },
+                    "ghcr.io/devcontainers/features/git:1": "latest"
                 }
-            }
-        }"#;
+            }"#,
+        )
+        .unwrap();
+        let options = config
+            .feature_options("ghcr.io/devcontainers/features/node:1")
+            .unwrap();
+        assert_eq!(options.get_or("version", "lts".to_string()), "20");
+        assert!(options.get_or("nodeGypDependencies", true));
+        // Mistyped values fall back to the default
+        assert!(options.get_or("installYarn", true));
+        assert_eq!(
+            options.keys().collect::<Vec<_>>(),
+            vec!["installYarn", "version"]
+        );
 
-        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
-        assert!(devcontainer.build.is_some());
-        let build = devcontainer.build.unwrap();
-        assert_eq!(build.dockerfile, Some("Dockerfile".to_string()));
-        assert_eq!(build.context, Some("..".to_string()));
+        assert!(config
+            .feature_options("ghcr.io/devcontainers/features/git:1")
+            .is_none());
+        assert!(config.feature_options("missing").is_none());
     }
 
     #[test]
-    #[cfg(feature = "vscode")]
-    fn test_with_features_and_extensions() {
-        let json = r#"{
-            "name": "Feature Test",
-            "image": "ubuntu:latest",
-            "features": {
-                "ghcr.io/devcontainers/features/docker-in-docker:2": {}
-            },
-            "extensions": [
-                "dbaeumer.vscode-eslint",
-                "esbenp.prettier-vscode"
-            ]
-        }"#;
+    fn test_rename_feature() {
+        let mut config: DevContainer = serde_json::from_str(
+            r#"{
+                "features": {
+                    "ghcr.io/devcontainers/features/node:2": { "version": "20" },
+                    "ghcr.io/devcontainers/features/git:1": {}
+                },
+                "overrideFeatureInstallOrder": [
+                    "ghcr.io/devcontainers/features/git",
+                    "ghcr.io/devcontainers/features/node:2"
+                ]
+            }"#,
+        )
+        .unwrap();
 
-        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
-        assert!(devcontainer.features.is_some());
-        assert!(devcontainer.extensions.is_some());
-        assert_eq!(devcontainer.extensions.unwrap().len(), 2);
+        assert!(config.rename_feature(
+            "ghcr.io/devcontainers/features/node:2",
+            "ghcr.io/devcontainers/features/node:3"
+        ));
+        let features = config.features.as_ref().unwrap();
+        assert!(!features.contains_key("ghcr.io/devcontainers/features/node:2"));
+        assert_eq!(
+            features["ghcr.io/devcontainers/features/node:3"],
+            serde_json::json!({ "version": "20" })
+        );
+        assert_eq!(
+            config.override_feature_install_order,
+            Some(vec![
+                "ghcr.io/devcontainers/features/git".to_string(),
+                "ghcr.io/devcontainers/features/node:3".to_string(),
+            ])
+        );
+        assert!(config.validate().is_ok());
+
+        assert!(!config.rename_feature("ghcr.io/devcontainers/features/node:2", "x"));
+        assert!(!DevContainer::default().rename_feature("a", "b"));
     }
 
     #[test]
-    #[cfg(not(feature = "vscode"))]
-    fn test_with_features_no_vscode() {
+    fn test_to_json_with_dockerfile_style() {
+        let legacy: DevContainer =
+            serde_json::from_str(r#"{"dockerFile":"Dockerfile","name":"x"}"#).unwrap();
+        let modern: DevContainer =
+            serde_json::from_str(r#"{"build":{"dockerfile":"Dockerfile"},"name":"x"}"#).unwrap();
+
+        let mut opts = SerializeOptions::default();
+        assert_eq!(
+            legacy.to_json_with(&opts),
+            serde_json::to_string(&legacy).unwrap()
+        );
+
+        opts.dockerfile = DockerfileStyle::Build;
+        assert_eq!(
+            legacy.to_json_with(&opts),
+            r#"{"name":"x","build":{"dockerfile":"Dockerfile"}}"#
+        );
+        assert_eq!(
+            modern.to_json_with(&opts),
+            serde_json::to_string(&modern).unwrap()
+        );
+
+        opts.dockerfile = DockerfileStyle::TopLevel;
+        assert_eq!(
+            modern.to_json_with(&opts),
+            r#"{"name":"x","dockerFile":"Dockerfile"}"#
+        );
+
+        // Other build settings keep the build object
+        let with_context = DevContainer {
+            build: Some(BuildConfig {
+                dockerfile: Some("Dockerfile".to_string()),
+                context: Some("..".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert_eq!(
+            with_context.to_json_with(&opts),
+            r#"{"dockerFile":"Dockerfile","build":{"context":".."}}"#
+        );
+    }
+
+    #[test]
+    fn test_effective_workspace_mount() {
+        let explicit = DevContainer {
+            workspace_mount: Some(
+                "source=${localWorkspaceFolder}/..,target=/src,type=bind".to_string(),
+            ),
+            workspace_folder: Some("/src/app".to_string()),
+            ..Default::default()
+        };
+        let mount = explicit.effective_workspace_mount().unwrap();
+        assert_eq!(mount.source.as_deref(), Some("${localWorkspaceFolder}/.."));
+        assert_eq!(mount.target.as_deref(), Some("/src"));
+
+        let with_folder = DevContainer {
+            workspace_folder: Some("/code".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            with_folder
+                .effective_workspace_mount()
+                .unwrap()
+                .to_short_string(),
+            "type=bind,source=${localWorkspaceFolder},target=/code"
+        );
+
+        let mount = DevContainer::default().effective_workspace_mount().unwrap();
+        assert_eq!(mount.source.as_deref(), Some("${localWorkspaceFolder}"));
+        assert_eq!(
+            mount.target.as_deref(),
+            Some("/workspaces/${localWorkspaceFolderBasename}")
+        );
+        assert_eq!(mount.mount_type, Some(MountType::Bind));
+
+        let invalid = DevContainer {
+            workspace_mount: Some(",".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(invalid.effective_workspace_mount(), None);
+    }
+
+    #[test]
+    fn test_feature_option_pointer() {
         let json = r#"{
-            "name": "Feature Test",
-            "image": "ubuntu:latest",
             "features": {
-                "ghcr.io/devcontainers/features/docker-in-docker:2": {}
+                "ghcr.io/devcontainers/features/common-utils:2": {
+                    "packages": ["curl", "jq"],
+                    "user": {"name": "dev", "uid": 1000}
+                }
             }
         }"#;
-
         let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
-        assert!(devcontainer.features.is_some());
+        let feature = "ghcr.io/devcontainers/features/common-utils:2";
+
+        assert_eq!(
+            devcontainer.feature_option_pointer(feature, "/packages/1"),
+            Some(&serde_json::json!("jq"))
+        );
+        assert_eq!(
+            devcontainer.feature_option_pointer(feature, "/user/uid"),
+            Some(&serde_json::json!(1000))
+        );
+        assert!(devcontainer
+            .feature_option_pointer(feature, "")
+            .is_some_and(serde_json::Value::is_object));
+        assert_eq!(
+            devcontainer.feature_option_pointer(feature, "/packages/2"),
+            None
+        );
+        assert_eq!(
+            devcontainer.feature_option_pointer(feature, "/missing"),
+            None
+        );
+        assert_eq!(
+            devcontainer.feature_option_pointer("other", "/packages"),
+            None
+        );
     }
 
     #[test]
-    fn test_port_forwarding() {
+    fn test_normalize_forward_ports() {
+        let mut devcontainer: DevContainer =
+            serde_json::from_str(r#"{"forwardPorts": [3001, 3000, 3000, "db:5432"]}"#).unwrap();
+        devcontainer.normalize_forward_ports();
+        assert_eq!(
+            serde_json::to_value(&devcontainer.forward_ports).unwrap(),
+            serde_json::json!([3000, 3001, "db:5432"])
+        );
+
+        let mut devcontainer: DevContainer = serde_json::from_str(
+            r#"{"forwardPorts": ["cache:6379", "9003-9005", 9000, "9000-9002", 9001, "db:5432", 8080]}"#,
+        )
+        .unwrap();
+        devcontainer.normalize_forward_ports();
+        assert_eq!(
+            serde_json::to_value(&devcontainer.forward_ports).unwrap(),
+            serde_json::json!([8080, "9000-9005", "cache:6379", "db:5432"])
+        );
+
+        let mut empty = DevContainer::default();
+        empty.normalize_forward_ports();
+        assert_eq!(empty.forward_ports, None);
+    }
+
+    #[test]
+    fn test_named_shell_strings() {
         let json = r#"{
-            "name": "Port Test",
-            "image": "node:18",
-            "forwardPorts": [3000, "db:5432"],
-            "portsAttributes": {
-                "3000": {
-                    "label": "Application",
-                    "onAutoForward": "notify"
-                }
-            }
+            "server": ["npm", "run", "dev", "--", "--host", "0.0.0.0"],
+            "greet": ["echo", "it's ready", ""],
+            "watch": "cargo watch -x check"
         }"#;
+        let command: LifecycleCommand = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            command.named_shell_strings(),
+            vec![
+                ("greet".to_string(), "echo 'it'\\''s ready' ''".to_string()),
+                (
+                    "server".to_string(),
+                    "npm run dev -- --host 0.0.0.0".to_string()
+                ),
+                ("watch".to_string(), "cargo watch -x check".to_string()),
+            ]
+        );
 
-        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
-        assert!(devcontainer.forward_ports.is_some());
-        assert!(devcontainer.ports_attributes.is_some());
+        let single = LifecycleCommand::Command(CommandSpec::Shell("make".to_string()));
+        assert!(single.named_shell_strings().is_empty());
     }
 
     #[test]
-    fn test_lifecycle_commands() {
+    fn test_builder_roundtrip() {
         let json = r#"{
-            "name": "Lifecycle Test",
-            "image": "node:18",
-            "postCreateCommand": "npm install",
-            "postStartCommand": ["npm", "run", "dev"]
+            "name": "app",
+            "image": "rust:1.80",
+            "features": {"ghcr.io/devcontainers/features/node:1": {"version": "20"}},
+            "forwardPorts": [3000],
+            "containerEnv": {"RUST_LOG": "debug"},
+            "postCreateCommand": "cargo fetch"
         }"#;
+        let original: DevContainer = serde_json::from_str(json).unwrap();
 
-        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
-        assert!(devcontainer.post_create_command.is_some());
-        assert!(devcontainer.post_start_command.is_some());
+        let rebuilt = original.to_builder().image("rust:1.81").build();
+        assert_eq!(rebuilt.image.as_deref(), Some("rust:1.81"));
+        assert_eq!(
+            DevContainer {
+                image: original.image.clone(),
+                ..rebuilt.clone()
+            },
+            original
+        );
+        assert_eq!(original.clone().into_builder().build(), original);
+
+        let built = DevContainerBuilder::new()
+            .name("app")
+            .dockerfile("Dockerfile")
+            .feature(
+                "ghcr.io/devcontainers/features/node:1",
+                serde_json::json!({}),
+            )
+            .forward_port(PortSpec::Number(8080))
+            .container_env("RUST_LOG", "info")
+            .remote_user("vscode")
+            .build();
+        assert_eq!(built.effective_dockerfile(), Some("Dockerfile"));
+        assert_eq!(built.feature_count(), 1);
+        assert_eq!(built.container_env.unwrap()["RUST_LOG"], "info");
+        assert_eq!(built.remote_user.as_deref(), Some("vscode"));
     }
 
     #[test]
-    fn test_environment_variables() {
+    fn test_lifecycle_command_merge_avoids_name_collisions() {
+        let single =
+            |command: &str| LifecycleCommand::Command(CommandSpec::Shell(command.to_string()));
+        let object = |entries: &[(&str, &str)]| {
+            LifecycleCommand::Object(
+                entries
+                    .iter()
+                    .map(|(name, command)| {
+                        (name.to_string(), CommandSpec::Shell(command.to_string()))
+                    })
+                    .collect(),
+            )
+        };
+
+        assert_eq!(
+            single("x").merge(&object(&[("base", "y"), ("base-2", "z")])),
+            object(&[("base", "y"), ("base-2", "z"), ("base-3", "x")])
+        );
+        assert_eq!(
+            object(&[("override", "y")]).merge(&single("x")),
+            object(&[("override", "y"), ("override-2", "x")])
+        );
+        assert_eq!(
+            single("x").merge(&single("y")),
+            object(&[("base", "x"), ("override", "y")])
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "docker-compose")]
+    fn test_to_compose_override_port_ranges() {
         let json = r#"{
-            "name": "Env Test",
-            "image": "python:3.9",
-            "containerEnv": {
-                "MY_VAR": "value",
-                "ANOTHER_VAR": "another_value"
-            },
-            "remoteUser": "vscode"
+            "service": "app",
+            "forwardPorts": ["40000-55000", 3000, "localhost:8080", "db:5432", 41000],
+            "appPort": ["3000", "9000-9001"]
         }"#;
 
         let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
-        assert!(devcontainer.container_env.is_some());
-        assert_eq!(devcontainer.remote_user, Some("vscode".to_string()));
+        assert_eq!(
+            devcontainer.to_compose_override(),
+            Some(serde_json::json!({
+                "services": {
+                    "app": {
+                        "ports": [
+                            "3000:3000",
+                            "8080:8080",
+                            "9000-9001:9000-9001",
+                            "40000-55000:40000-55000"
+                        ]
+                    }
+                }
+            }))
+        );
     }
 
     #[test]
-    fn test_serialization_roundtrip() {
-        let mut devcontainer = DevContainer::default();
-        devcontainer.name = Some("Test".to_string());
-        devcontainer.image = Some("ubuntu:latest".to_string());
-        #[cfg(feature = "vscode")]
-        {
-            devcontainer.extensions = Some(vec!["test.extension".to_string()]);
-        }
+    fn test_wait_for_initialize_and_unknown() {
+        let json = r#"{ "image": "node:18", "waitFor": "initializeCommand" }"#;
+        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
+        assert_eq!(devcontainer.wait_for, Some(WaitFor::Initialize));
+        assert!(devcontainer.lint().is_empty());
 
-        let json = serde_json::to_string(&devcontainer).unwrap();
-        let parsed: DevContainer = serde_json::from_str(&json).unwrap();
-        assert_eq!(devcontainer, parsed);
+        let json = r#"{ "image": "node:18", "waitFor": "bogus" }"#;
+        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            devcontainer.wait_for,
+            Some(WaitFor::Other("bogus".to_string()))
+        );
+        let diagnostics = devcontainer.lint();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+        assert_eq!(diagnostics[0].field, "waitFor");
+        assert_eq!(
+            serde_json::to_string(&devcontainer).unwrap(),
+            r#"{"image":"node:18","waitFor":"bogus"}"#
+        );
     }
 
-    #[cfg(not(feature = "allow-unknown-fields"))]
     #[test]
-    fn test_unknown_fields_rejected_by_default() {
-        let json = r#"{
-            "name": "Test",
-            "image": "ubuntu:latest",
-            "unknownField": "should fail"
-        }"#;
+    fn test_visit_strings_matches_visit_strings_mut() {
+        let mut config: DevContainer = serde_json::from_str(
+            r#"{
+                "name": "demo",
+                "build": { "dockerfile": "Dockerfile", "args": { "V": "1" } },
+                "features": { "ghcr.io/devcontainers/features/node:1": { "version": "20" } },
+                "remoteEnv": { "B": "b" },
+                "postStartCommand": { "server": ["npm", "start"] },
+                "mounts": [{ "source": "cache", "target": "/cache" }],
+                "capAdd": ["SYS_PTRACE"]
+            }"#,
+        )
+        .unwrap();
 
-        let result: Result<DevContainer, _> = serde_json::from_str(json);
-        assert!(result.is_err());
+        let mut visited = Vec::new();
+        config.visit_strings(|value| visited.push(value.to_string()));
+        let mut visited_mut = Vec::new();
+        config.visit_strings_mut(|value| visited_mut.push(value.clone()));
+        assert_eq!(visited, visited_mut);
+        assert_eq!(visited.len(), 10);
     }
 
+    #[test]
+    fn test_validate_image_reference_uppercase_warns() {
+        for image in ["Ubuntu", "MCR.microsoft.com/Devcontainers/rust:1-Bookworm"] {
+            let config = DevContainer {
+                image: Some(image.to_string()),
+                ..Default::default()
+            };
+            let diagnostic = config.validate_image_reference().unwrap();
+            assert_eq!(diagnostic.severity, Severity::Warning, "{}", image);
+            assert_eq!(diagnostic.field, "image");
+            assert!(config.validate().is_ok(), "{}", image);
+        }
+
+        // Uppercase tags are already valid
+        let config = DevContainer {
+            image: Some("ubuntu:Latest".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(config.validate_image_reference(), None);
+    }
+
+    #[test]
     #[cfg(feature = "allow-unknown-fields")]
+    fn test_labeled_port_unknown_fields() {
+        let json = r#"{ "forwardPorts": [{ "port": 3000, "label": "App", "protocol": "https" }] }"#;
+        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
+        let Some(PortSpec::Labeled(labeled)) = devcontainer
+            .forward_ports
+            .as_ref()
+            .and_then(|ports| ports.first())
+        else {
+            panic!("expected a labeled port");
+        };
+        assert_eq!(labeled.port, 3000);
+        assert_eq!(
+            labeled.additional_fields.get("protocol"),
+            Some(&serde_json::json!("https"))
+        );
+
+        let parsed: DevContainer = json.parse().unwrap();
+        assert_eq!(parsed.forward_ports, Some(vec![PortSpec::Number(3000)]));
+    }
+
     #[test]
-    fn test_unknown_fields_captured_with_feature() {
+    #[cfg(feature = "vscode")]
+    fn test_to_json_with_property_naming() {
         let json = r#"{
-            "name": "Test",
-            "image": "ubuntu:latest",
-            "unknownField": "should be captured",
-            "anotherUnknown": 42
+            "extensions": ["rust-lang.rust-analyzer"],
+            "settings": { "editor.tabSize": 2, "files.eol": "\n" },
+            "customizations": {
+                "vscode": {
+                    "extensions": ["tamasfe.even-better-toml", "rust-lang.rust-analyzer"],
+                    "settings": { "editor.tabSize": 4 }
+                },
+                "jetbrains": { "backend": "RustRover" }
+            }
         }"#;
+        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
 
-        let result: DevContainer = serde_json::from_str(json).unwrap();
-        assert_eq!(result.name, Some("Test".to_string()));
-        assert_eq!(result.additional_fields.len(), 2);
-        assert!(result.additional_fields.contains_key("unknownField"));
-        assert!(result.additional_fields.contains_key("anotherUnknown"));
-    }
+        let mut opts = SerializeOptions::default();
+        assert_eq!(
+            devcontainer.to_json_with(&opts),
+            serde_json::to_string(&devcontainer).unwrap()
+        );
 
-    #[test]
-    fn test_default_implementations() {
-        // Test Default for DevContainer
-        let devcontainer = DevContainer::default();
-        assert_eq!(devcontainer.name, None);
-        assert_eq!(devcontainer.image, None);
+        opts.naming = PropertyNaming::Current;
+        let current: serde_json::Value =
+            serde_json::from_str(&devcontainer.to_json_with(&opts)).unwrap();
+        assert_eq!(
+            current,
+            serde_json::json!({
+                "customizations": {
+                    "vscode": {
+                        "extensions": ["rust-lang.rust-analyzer", "tamasfe.even-better-toml"],
+                        "settings": { "editor.tabSize": 4, "files.eol": "\n" }
+                    },
+                    "jetbrains": { "backend": "RustRover" }
+                }
+            })
+        );
 
-        // Test Default for BuildConfig
-        let build_config = BuildConfig::default();
-        assert_eq!(build_config.dockerfile, None);
-        assert_eq!(build_config.context, None);
+        opts.naming = PropertyNaming::Historical;
+        let historical: serde_json::Value =
+            serde_json::from_str(&devcontainer.to_json_with(&opts)).unwrap();
+        assert_eq!(
+            historical,
+            serde_json::json!({
+                "extensions": ["rust-lang.rust-analyzer", "tamasfe.even-better-toml"],
+                "settings": { "editor.tabSize": 4, "files.eol": "\n" },
+                "customizations": {
+                    "jetbrains": { "backend": "RustRover" }
+                }
+            })
+        );
 
-        // Test Default for PortAttributes
-        let port_attrs = PortAttributes::default();
-        assert_eq!(port_attrs.label, None);
+        // Both knobs apply together
+        opts.dockerfile = DockerfileStyle::TopLevel;
+        let with_build = DevContainer {
+            build: Some(BuildConfig {
+                dockerfile: Some("Dockerfile".to_string()),
+                ..Default::default()
+            }),
+            ..devcontainer
+        };
+        let output: serde_json::Value =
+            serde_json::from_str(&with_build.to_json_with(&opts)).unwrap();
+        assert_eq!(output["dockerFile"], "Dockerfile");
+        assert_eq!(output["extensions"], historical["extensions"]);
+    }
 
-        // Test Default for MountSpec
-        let mount = MountSpec::default();
-        assert_eq!(mount.source, None);
+    #[test]
+    #[cfg(feature = "ordered-env")]
+    fn test_ordered_env_minimal_json() {
+        let json = r#"{
+            "containerEnv": { "FOO": "1", "ABC": "2", "BAR": "3" },
+            "remoteEnv": { "Z": "z", "A": "" },
+            "mounts": []
+        }"#;
+        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
+
+        assert_eq!(
+            devcontainer.to_minimal_json(),
+            r#"{"containerEnv":{"FOO":"1","ABC":"2","BAR":"3"},"remoteEnv":{"Z":"z","A":""}}"#
+        );
     }
 
     #[test]
-    fn test_default_with_modifications() {
-        // Demonstrate using Default as a builder pattern
-        let mut devcontainer = DevContainer::default();
-        devcontainer.name = Some("Test Container".to_string());
-        devcontainer.image = Some("ubuntu:latest".to_string());
+    #[cfg(all(feature = "ordered-env", feature = "docker-compose"))]
+    fn test_ordered_env_compose_override() {
+        let json = r#"{
+            "service": "app",
+            "containerEnv": { "FOO": "1", "ABC": "2", "BAR": "3" }
+        }"#;
+        let devcontainer: DevContainer = serde_json::from_str(json).unwrap();
 
-        let json = serde_json::to_string(&devcontainer).unwrap();
-        let parsed: DevContainer = serde_json::from_str(&json).unwrap();
-        assert_eq!(parsed.name, Some("Test Container".to_string()));
-        assert_eq!(parsed.image, Some("ubuntu:latest".to_string()));
+        assert_eq!(
+            devcontainer.to_compose_override().unwrap()["services"]["app"]["environment"],
+            serde_json::json!(["FOO=1", "ABC=2", "BAR=3"])
+        );
     }
 }